@@ -0,0 +1,17 @@
+// Draws a repeating texture shifted by an offset, so it can scroll without touching the mesh.
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct ScrollingMaterial {
+    color: vec4<f32>,
+    offset: vec2<f32>,
+    repeat: vec2<f32>,
+};
+
+@group(1) @binding(0) var<uniform> material: ScrollingMaterial;
+@group(1) @binding(1) var base_texture: texture_2d<f32>;
+@group(1) @binding(2) var base_sampler: sampler;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    return material.color * textureSample(base_texture, base_sampler, mesh.uv * material.repeat + material.offset);
+}
@@ -0,0 +1,335 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::reflect::TypePath;
+use bevy_egui::{egui, EguiContexts, EguiSettings};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppState,
+    assets::GameAssets,
+    game::{Bird, BirdCrashed, BirdFlapped, BirdScored, CrashCause, GameState, Pipe, RunFinished},
+    menu::MenuScreen,
+    storage, touch,
+};
+
+const ACHIEVEMENTS_FILENAME: &str = "achievements.ron";
+/// How long an unlock notification stays on screen.
+const TOAST_SECS: f32 = 3.0;
+/// The most notifications shown at once. Extras wait their turn.
+const MAX_VISIBLE_TOASTS: usize = 3;
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(storage::load::<AchievementProgress>(ACHIEVEMENTS_FILENAME))
+            .init_resource::<RunCounters>()
+            .init_resource::<Toasts>()
+            .add_systems(OnEnter(GameState::Playing), reset_run_counters)
+            .add_systems(Update, (
+                update_counters,
+                check_achievements.after(update_counters),
+            ).run_if(in_state(AppState::InGame)))
+            .add_systems(Update, achievements_screen_ui
+                .run_if(in_state(AppState::MainMenu))
+                .run_if(resource_equals(MenuScreen::Achievements)))
+            .add_systems(Update, show_toasts.run_if(not(in_state(AppState::Loading))));
+    }
+}
+
+/// Achievement definitions, loaded from a `.achievements.ron` file.
+#[derive(Asset, TypePath, Deserialize)]
+pub struct AchievementList {
+    pub achievements: Vec<AchievementDef>,
+}
+
+impl AchievementList {
+    pub fn get(&self, id: &str) -> Option<&AchievementDef> {
+        self.achievements.iter().find(|achievement| achievement.id == id)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AchievementDef {
+    /// Stable identifier used to remember unlocks. Changing it resets the achievement.
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+pub enum Condition {
+    /// A counter tracked over a single run reached a value.
+    Run { counter: RunCounter, at_least: u64 },
+    /// A counter accumulated over every run reached a value.
+    Total { counter: TotalCounter, at_least: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum RunCounter {
+    Score,
+    Flaps,
+    /// The most pipes passed in a row without flapping while above the center of the next gap.
+    PipesWithoutFlapAboveGapCenter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum TotalCounter {
+    Runs,
+    Flaps,
+    PipesPassed,
+    /// Crashes into a pipe before scoring any points.
+    DeathsOnFirstPipe,
+}
+
+/// Unlocked achievements and the cumulative counters their conditions are checked against.
+#[derive(Default, Deserialize, Serialize, Resource)]
+#[serde(default)]
+pub struct AchievementProgress {
+    /// Unlock time of each achievement by id, in seconds since the Unix epoch.
+    pub unlocked: BTreeMap<String, u64>,
+    pub counters: BTreeMap<TotalCounter, u64>,
+}
+
+impl AchievementProgress {
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.contains_key(id)
+    }
+
+    fn counter(&self, counter: TotalCounter) -> u64 {
+        self.counters.get(&counter).copied().unwrap_or(0)
+    }
+
+    fn add(&mut self, counter: TotalCounter, amount: u64) {
+        *self.counters.entry(counter).or_default() += amount;
+    }
+}
+
+#[derive(Default, Resource)]
+struct RunCounters {
+    score: u64,
+    flaps: u64,
+    clean_streak: u64,
+    best_clean_streak: u64,
+}
+
+impl RunCounters {
+    fn get(&self, counter: RunCounter) -> u64 {
+        match counter {
+            RunCounter::Score => self.score,
+            RunCounter::Flaps => self.flaps,
+            RunCounter::PipesWithoutFlapAboveGapCenter => self.best_clean_streak,
+        }
+    }
+}
+
+impl Condition {
+    fn is_met(&self, run: &RunCounters, progress: &AchievementProgress) -> bool {
+        match *self {
+            Self::Run { counter, at_least } => run.get(counter) >= at_least,
+            Self::Total { counter, at_least } => progress.counter(counter) >= at_least,
+        }
+    }
+}
+
+struct Toast {
+    title: String,
+    text: String,
+    age: f32,
+}
+
+/// Unlock notifications waiting to be shown or on screen.
+#[derive(Default, Resource)]
+struct Toasts(VecDeque<Toast>);
+
+fn reset_run_counters(
+    mut run_counters: ResMut<RunCounters>,
+) {
+    *run_counters = default();
+}
+
+/// The counters kept for the current run and across all runs.
+#[derive(SystemParam)]
+struct Counters<'w> {
+    run_counters: ResMut<'w, RunCounters>,
+    progress: ResMut<'w, AchievementProgress>,
+}
+
+fn update_counters(
+    mut flapped: EventReader<BirdFlapped>,
+    mut scored: EventReader<BirdScored>,
+    mut crashed: EventReader<BirdCrashed>,
+    mut run_finished: EventReader<RunFinished>,
+    mut counters: Counters,
+    bird_q: Query<&Transform, With<Bird>>,
+    pipe_q: Query<&Transform, With<Pipe>>,
+) {
+    let Counters { run_counters, progress } = &mut counters;
+    for _ in flapped.read() {
+        run_counters.flaps += 1;
+        progress.add(TotalCounter::Flaps, 1);
+
+        // Pipes are centered on their gap, so the next pipe ahead of the bird gives the gap center.
+        let Ok(bird_transform) = bird_q.get_single() else {
+            continue;
+        };
+        let bird_pos = bird_transform.translation;
+        let next_gap_y = pipe_q.iter()
+            .map(|transform| transform.translation)
+            .filter(|pipe_pos| pipe_pos.x >= bird_pos.x)
+            .min_by(|a, b| a.x.total_cmp(&b.x))
+            .map(|pipe_pos| pipe_pos.y);
+        if next_gap_y.is_some_and(|gap_y| bird_pos.y > gap_y) {
+            run_counters.clean_streak = 0;
+        }
+    }
+
+    for _ in scored.read() {
+        run_counters.score += 1;
+        run_counters.clean_streak += 1;
+        run_counters.best_clean_streak = run_counters.best_clean_streak.max(run_counters.clean_streak);
+        progress.add(TotalCounter::PipesPassed, 1);
+    }
+
+    for crash in crashed.read() {
+        let hit_pipe = matches!(crash.cause, CrashCause::PipeTop | CrashCause::PipeBottom);
+        if hit_pipe && run_counters.score == 0 {
+            progress.add(TotalCounter::DeathsOnFirstPipe, 1);
+        }
+    }
+
+    for _ in run_finished.read() {
+        progress.add(TotalCounter::Runs, 1);
+        storage::save(ACHIEVEMENTS_FILENAME, &**progress);
+    }
+}
+
+fn check_achievements(
+    assets: Res<GameAssets>,
+    achievement_lists: Res<Assets<AchievementList>>,
+    run_counters: Res<RunCounters>,
+    mut progress: ResMut<AchievementProgress>,
+    mut toasts: ResMut<Toasts>,
+) {
+    if !run_counters.is_changed() && !progress.is_changed() {
+        return;
+    }
+
+    let Some(list) = achievement_lists.get(&assets.achievements) else {
+        return;
+    };
+
+    let mut unlocked_any = false;
+    for achievement in &list.achievements {
+        if progress.is_unlocked(&achievement.id) || !achievement.condition.is_met(&run_counters, &progress) {
+            continue;
+        }
+
+        info!("Unlocked achievement: {}", achievement.id);
+        progress.unlocked.insert(achievement.id.clone(), storage::unix_timestamp());
+        toasts.0.push_back(Toast {
+            title: achievement.name.clone(),
+            text: achievement.description.clone(),
+            age: 0.0,
+        });
+        unlocked_any = true;
+    }
+
+    if unlocked_any {
+        storage::save(ACHIEVEMENTS_FILENAME, &*progress);
+    }
+}
+
+fn show_toasts(
+    time: Res<Time>,
+    mut toasts: ResMut<Toasts>,
+    mut egui_ctx: EguiContexts,
+) {
+    if toasts.0.is_empty() {
+        return;
+    }
+
+    let dt = time.delta_seconds();
+    for toast in toasts.0.iter_mut().take(MAX_VISIBLE_TOASTS) {
+        toast.age += dt;
+    }
+    toasts.0.retain(|toast| toast.age < TOAST_SECS);
+
+    egui::Area::new("achievement_toasts")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 10.0])
+        .interactable(false)
+        .show(egui_ctx.ctx_mut(), |ui| {
+            for toast in toasts.0.iter().take(MAX_VISIBLE_TOASTS) {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(250.0);
+                    ui.label(egui::RichText::new("Achievement Unlocked").size(14.0));
+                    ui.label(egui::RichText::new(&toast.title).size(24.0).color(egui::Color32::YELLOW));
+                    ui.label(toast.text.as_str());
+                });
+            }
+        });
+}
+
+fn achievements_screen_ui(
+    touches: Res<Touches>,
+    egui_settings: Res<EguiSettings>,
+    assets: Res<GameAssets>,
+    achievement_lists: Res<Assets<AchievementList>>,
+    progress: Res<AchievementProgress>,
+    mut menu_screen: ResMut<MenuScreen>,
+    mut egui_ctx: EguiContexts,
+) {
+    let window = egui::Window::new("Achievements")
+        .title_bar(false)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -50.0]);
+    window.show(egui_ctx.ctx_mut(), |ui| {
+        ui.set_width(320.0);
+        ui.vertical_centered(|ui| {
+            ui.label(egui::RichText::new("Achievements").size(30.0));
+
+            egui::ScrollArea::vertical()
+                .max_height(350.0)
+                .show(ui, |ui| {
+                    let achievements = achievement_lists.get(&assets.achievements)
+                        .map(|list| list.achievements.as_slice())
+                        .unwrap_or(&[]);
+                    for achievement in achievements {
+                        let unlocked = progress.unlocked.get(&achievement.id);
+                        let color = if unlocked.is_some() {
+                            egui::Color32::YELLOW
+                        } else {
+                            egui::Color32::GRAY
+                        };
+
+                        ui.group(|ui| {
+                            ui.set_width(290.0);
+                            ui.label(egui::RichText::new(&achievement.name).size(20.0).color(color));
+                            ui.label(achievement.description.as_str());
+                            match (unlocked, achievement.condition) {
+                                (Some(timestamp), _) => {
+                                    ui.label(format!("Unlocked {}", storage::format_date(*timestamp)));
+                                }
+                                (None, Condition::Total { counter, at_least }) => {
+                                    let current = progress.counter(counter).min(at_least);
+                                    ui.label(format!("{}/{}", current, at_least));
+                                }
+                                (None, Condition::Run { .. }) => {}
+                            }
+                        });
+                    }
+                });
+
+            ui.add_space(10.0);
+            let back = ui.button(egui::RichText::new("Back").size(30.0));
+            if back.clicked() || touch::just_touched(&touches, &egui_settings, back.rect) {
+                *menu_screen = MenuScreen::Main;
+            }
+        });
+    });
+}
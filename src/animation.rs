@@ -1,7 +1,18 @@
-use std::time::Duration;
+use std::{collections::VecDeque, time::Duration};
 
 use bevy::prelude::*;
-use bevy::reflect::{TypePath, TypeUuid};
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    reflect::{TypePath, TypeUuid},
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{assets, atlas::SpriteAtlas};
+
+/// The shortest time a frame can be shown for.
+const MIN_FRAME_TIME: Duration = Duration::from_millis(1);
 
 pub struct AnimationPlugin;
 
@@ -9,49 +20,643 @@ impl Plugin for AnimationPlugin {
     fn build(&self, app: &mut App) {
         app
             .init_asset::<Animation>()
-            .add_systems(Update, animate_sprites);
+            .register_asset_loader(AnimationLoader)
+            .add_event::<AnimationEvent>()
+            .add_event::<AnimationFinished>()
+            .add_systems(Update, (
+                restart_modified_animations,
+                animate_sprites.after(restart_modified_animations),
+                log_animation_events.after(animate_sprites),
+                log_finished_animations.after(animate_sprites),
+            ));
     }
 }
 
-#[derive(Asset, TypePath, TypeUuid, Deref)]
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub enum LoopMode {
+    /// Play through once and stop on the last frame.
+    Once,
+    #[default]
+    Repeat,
+    /// Play forwards then backwards, without repeating the first and last frames.
+    PingPong,
+}
+
+#[derive(Debug, Deserialize)]
+struct FrameDef {
+    index: usize,
+    /// Overrides the animation's `frame_ms` for this frame.
+    #[serde(default)]
+    ms: Option<u64>,
+    /// Names of `AnimationEvent`s sent when playback reaches this frame.
+    #[serde(default)]
+    events: Vec<String>,
+}
+
+/// The contents of a `.anim.ron` file.
+#[derive(Debug, Deserialize)]
+struct AnimationFile {
+    /// Path to the `.atlas.ron` the frame indices refer to, relative to the animation.
+    atlas: String,
+    #[serde(default)]
+    mode: LoopMode,
+    /// How long each frame lasts unless it sets its own duration.
+    frame_ms: u64,
+    frames: Vec<FrameDef>,
+}
+
+impl AnimationFile {
+    /// Builds the animation, with frames that don't set a duration lasting `frame_ms`.
+    fn into_animation(self, atlas: Handle<SpriteAtlas>) -> Result<Animation, AnimationLoaderError> {
+        if self.frames.is_empty() {
+            return Err(AnimationLoaderError::NoFrames);
+        }
+
+        let frame_ms = self.frame_ms;
+        let frames = self.frames.into_iter()
+            .map(|frame| Frame {
+                index: frame.index,
+                duration: Duration::from_millis(frame.ms.unwrap_or(frame_ms)),
+                events: frame.events,
+            });
+        Ok(Animation::from_frames(frames, self.mode, atlas))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Frame {
+    /// Index into the atlas.
+    pub index: usize,
+    pub duration: Duration,
+    /// Names of `AnimationEvent`s sent when playback reaches this frame.
+    pub events: Vec<String>,
+}
+
+#[derive(Asset, TypePath, TypeUuid)]
 #[uuid = "ae6a74db-f6fa-43c4-ac16-01d13b50e4c6"]
-pub struct Animation(benimator::Animation);
+pub struct Animation {
+    frames: Vec<Frame>,
+    mode: LoopMode,
+    /// The atlas this animation's frame indices refer to.
+    pub atlas: Handle<SpriteAtlas>,
+}
 
 impl Animation {
-    pub fn from_indices(indices: impl IntoIterator<Item = usize>, frame_time: Duration) -> Self {
-        Self(benimator::Animation::from_indices(indices, benimator::FrameRate::from_frame_duration(frame_time)))
+    /// Creates an animation from its frames, in the order they play.
+    pub fn from_frames(
+        frames: impl IntoIterator<Item = Frame>,
+        mode: LoopMode,
+        atlas: Handle<SpriteAtlas>,
+    ) -> Self {
+        let frames = frames.into_iter()
+            // Frames need to last some time so playback always moves forward.
+            .map(|frame| Frame { duration: frame.duration.max(MIN_FRAME_TIME), ..frame })
+            .collect();
+        Self { frames, mode, atlas }
+    }
+
+    /// Number of steps in one cycle of the animation.
+    fn cycle_len(&self) -> usize {
+        match self.mode {
+            LoopMode::PingPong if self.frames.len() > 2 => self.frames.len() * 2 - 2,
+            _ => self.frames.len(),
+        }
+    }
+
+    /// The frame shown at a position within a cycle.
+    fn frame_at(&self, position: usize) -> Option<&Frame> {
+        let position = match self.mode {
+            LoopMode::PingPong if position >= self.frames.len() => self.cycle_len() - position,
+            _ => position,
+        };
+        self.frames.get(position)
     }
 }
 
-#[derive(Default, Component, Deref, DerefMut)]
-pub struct AnimationState(benimator::State);
+#[derive(Default)]
+pub struct AnimationLoader;
+
+#[derive(Debug, Error)]
+pub enum AnimationLoaderError {
+    #[error("Could not read asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("Animation has no frames")]
+    NoFrames,
+}
+
+impl AssetLoader for AnimationLoader {
+    type Asset = Animation;
+    type Settings = ();
+    type Error = AnimationLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let file: AnimationFile = ron::de::from_bytes(&bytes)?;
+            let atlas = load_context.load(assets::relative_path(load_context, &file.atlas));
+            file.into_animation(atlas)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.ron"]
+    }
+}
+
+/// Playback position and controls for an entity's animation.
+#[derive(Component)]
+pub struct AnimationState {
+    /// Position within one cycle of the animation. Ping-pong cycles visit the frames twice.
+    position: usize,
+    /// Time spent on the current frame.
+    elapsed: Duration,
+    /// Whether the first frame has been reached, so its events have been sent.
+    started: bool,
+    ended: bool,
+    /// Frame to jump to on the next update.
+    seek_to: Option<usize>,
+    speed: f32,
+    paused: bool,
+    reversed: bool,
+    /// Stop at the end of the current cycle even if the animation loops.
+    one_shot: bool,
+}
+
+impl Default for AnimationState {
+    fn default() -> Self {
+        Self {
+            position: 0,
+            elapsed: Duration::ZERO,
+            started: false,
+            ended: false,
+            seek_to: None,
+            speed: 1.0,
+            paused: false,
+            reversed: false,
+            one_shot: false,
+        }
+    }
+}
+
+impl AnimationState {
+    /// Restarts playback from the beginning, keeping the speed, direction and pause state.
+    pub fn reset(&mut self) {
+        *self = Self {
+            speed: self.speed,
+            paused: self.paused,
+            reversed: self.reversed,
+            ..default()
+        };
+    }
+
+    /// Whether an animation that plays once has reached its end.
+    pub fn is_ended(&self) -> bool {
+        self.ended
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.0);
+    }
+
+    /// Stops playback on the current frame.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    /// Plays the animation backwards, starting from the last frame.
+    pub fn set_reversed(&mut self, reversed: bool) {
+        self.reversed = reversed;
+    }
+
+    /// Jumps to a frame of the animation on the next update, sending its events.
+    pub fn seek(&mut self, frame: usize) {
+        self.seek_to = Some(frame);
+        self.ended = false;
+    }
+
+    /// Plays the current animation until the end of its cycle, then stops and sends an
+    /// `AnimationFinished` event.
+    pub fn play_once(&mut self) {
+        self.reset();
+        self.one_shot = true;
+    }
+
+    pub fn sprite_index(&self, animation: &Animation) -> usize {
+        animation.frame_at(self.position)
+            .map(|frame| frame.index)
+            .unwrap_or(0)
+    }
+
+    /// Advances playback, calling `on_enter` for every frame reached along the way. Returns
+    /// whether a full cycle of the animation finished.
+    fn update(&mut self, animation: &Animation, delta: Duration, mut on_enter: impl FnMut(&Frame)) -> bool {
+        let cycle_len = animation.cycle_len();
+        if cycle_len == 0 {
+            return false;
+        }
+
+        if let Some(frame) = self.seek_to.take() {
+            self.position = frame.min(cycle_len - 1);
+            self.elapsed = Duration::ZERO;
+            self.started = true;
+            on_enter(animation.frame_at(self.position).unwrap());
+        }
+        if self.ended || self.paused {
+            return false;
+        }
+
+        // Handle animations that were swapped out for a shorter one.
+        self.position = self.position.min(cycle_len - 1);
+        if !self.started {
+            self.started = true;
+            if self.reversed {
+                self.position = cycle_len - 1;
+            }
+            on_enter(animation.frame_at(self.position).unwrap());
+        }
+        self.elapsed += delta.mul_f32(self.speed);
+
+        let plays_once = self.one_shot || matches!(animation.mode, LoopMode::Once);
+        let mut completed = false;
+        loop {
+            let duration = animation.frame_at(self.position).unwrap().duration;
+            if self.elapsed < duration {
+                break;
+            }
+            self.elapsed -= duration;
+
+            let at_end = if self.reversed { self.position == 0 } else { self.position + 1 == cycle_len };
+            if at_end && plays_once {
+                self.ended = true;
+                self.elapsed = Duration::ZERO;
+                return true;
+            }
+
+            completed |= at_end;
+            self.position = match (self.reversed, at_end) {
+                (false, false) => self.position + 1,
+                (false, true) => 0,
+                (true, false) => self.position - 1,
+                (true, true) => cycle_len - 1,
+            };
+            on_enter(animation.frame_at(self.position).unwrap());
+        }
+        completed
+    }
+}
 
 #[derive(Default, Component)]
 pub struct Play;
 
+/// Sent when an entity's animation stops after playing once.
+#[derive(Debug, Clone, Event)]
+pub struct AnimationFinished {
+    pub entity: Entity,
+    pub animation: Handle<Animation>,
+}
+
+/// Animations to play after the current one, each starting when the one before it ends or
+/// finishes a cycle.
+#[derive(Default, Component, Deref, DerefMut)]
+pub struct AnimationQueue(pub VecDeque<Handle<Animation>>);
+
+/// Sent when an entity's animation reaches a frame tagged with an event.
+#[derive(Debug, Clone, Event)]
+pub struct AnimationEvent {
+    pub entity: Entity,
+    pub name: String,
+}
+
+/// When a state machine may leave a state for a newly requested one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SwitchRule {
+    #[default]
+    Immediately,
+    /// Wait until the animation finishes its current cycle, or ends if it plays once.
+    FinishCycle,
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    pub animation: Handle<Animation>,
+    /// Playback speed multiplier.
+    pub speed: f32,
+    /// When this clip can be left for another one.
+    pub exit: SwitchRule,
+}
+
+impl Default for AnimationClip {
+    fn default() -> Self {
+        Self {
+            animation: default(),
+            speed: 1.0,
+            exit: default(),
+        }
+    }
+}
+
+/// Switches an entity's `Handle<Animation>` between clips as states are requested. States are
+/// indices into the clip list.
+#[derive(Component)]
+pub struct AnimationStateMachine {
+    clips: Vec<AnimationClip>,
+    current: usize,
+    requested: usize,
+    /// Switch to the requested state on the next update regardless of the current clip's exit rule.
+    forced: bool,
+}
+
+impl AnimationStateMachine {
+    pub fn new(clips: Vec<AnimationClip>, initial: usize) -> Self {
+        Self {
+            clips,
+            current: initial,
+            requested: initial,
+            forced: false,
+        }
+    }
+
+    /// The clip to start playing with, for the entity's `Handle<Animation>`.
+    pub fn initial_animation(&self) -> Handle<Animation> {
+        self.clips.get(self.current)
+            .map(|clip| clip.animation.clone())
+            .unwrap_or_default()
+    }
+
+    /// Switches to a state once the current clip's exit rule allows it.
+    pub fn request(&mut self, state: usize) {
+        if !self.forced {
+            self.requested = state;
+        }
+    }
+
+    /// Switches to a state on the next update, restarting it if it's already playing.
+    pub fn force(&mut self, state: usize) {
+        self.requested = state;
+        self.forced = true;
+    }
+
+    fn speed(&self) -> f32 {
+        self.clips.get(self.current).map(|clip| clip.speed).unwrap_or(1.0)
+    }
+
+    fn can_switch(&self, cycle_finished: bool) -> bool {
+        if self.forced {
+            return true;
+        }
+        if self.requested == self.current {
+            return false;
+        }
+        match self.clips.get(self.current).map(|clip| clip.exit) {
+            Some(SwitchRule::FinishCycle) => cycle_finished,
+            _ => true,
+        }
+    }
+
+    /// Moves to the requested state and returns its animation.
+    fn switch(&mut self) -> Handle<Animation> {
+        self.current = self.requested;
+        self.forced = false;
+        self.initial_animation()
+    }
+}
+
+/// Restarts sprites whose animation was reloaded, since their current frame may no longer exist.
+fn restart_modified_animations(
+    mut events: EventReader<AssetEvent<Animation>>,
+    mut query: Query<(&mut AnimationState, &Handle<Animation>)>,
+) {
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+
+        debug!("Animation {:?} was modified", id);
+        for (mut state, anim_handle) in query.iter_mut() {
+            if anim_handle.id() == *id {
+                state.reset();
+            }
+        }
+    }
+}
+
+/// Playing sprites, with the state machine or queue that picks their next animation.
+type PlayingQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut AnimationState,
+        &'static mut TextureAtlasSprite,
+        &'static mut Handle<TextureAtlas>,
+        &'static mut Handle<Animation>,
+        Option<&'static mut AnimationStateMachine>,
+        Option<&'static mut AnimationQueue>,
+    ),
+    With<Play>,
+>;
+
 pub fn animate_sprites(
     time: Res<Time>,
     animations: Res<Assets<Animation>>,
-    mut query: Query<
-        (
-            &mut AnimationState,
-            &mut TextureAtlasSprite,
-            &Handle<Animation>,
-        ),
-        With<Play>,
-    >,
+    sprite_atlases: Res<Assets<SpriteAtlas>>,
+    mut animation_events: EventWriter<AnimationEvent>,
+    mut finished_events: EventWriter<AnimationFinished>,
+    mut query: PlayingQuery,
 ) {
-    for (mut player, mut texture, anim_handle) in query.iter_mut() {
+    for (entity, mut player, mut texture, mut texture_atlas, mut anim_handle, mut machine, mut queue) in query.iter_mut() {
+        // Forced switches happen before playback so the new clip starts this frame.
+        if let Some(machine) = machine.as_mut().filter(|machine| machine.forced) {
+            *anim_handle = machine.switch();
+            player.reset();
+        }
+
         // Get the animation from the handle.
-        let animation = match animations.get(anim_handle) {
+        let animation = match animations.get(&*anim_handle) {
             Some(anim) => anim,
             None => continue,
         };
 
         // Update the animation state.
-        player.update(animation, time.delta());
+        let speed = machine.as_ref().map(|machine| machine.speed()).unwrap_or(1.0);
+        let send_events = |frame: &Frame| {
+            for name in &frame.events {
+                animation_events.send(AnimationEvent { entity, name: name.clone() });
+            }
+        };
+        let was_ended = player.is_ended();
+        let cycle_finished = player.update(animation, time.delta().mul_f32(speed), send_events) || player.is_ended();
+        if player.is_ended() && !was_ended {
+            finished_events.send(AnimationFinished { entity, animation: anim_handle.clone() });
+        }
+
+        // Let the state machine or queue move on now that a cycle may have finished.
+        let next = match machine.as_mut().filter(|machine| machine.can_switch(cycle_finished)) {
+            Some(machine) => Some(machine.switch()),
+            None if cycle_finished => queue.as_mut().and_then(|queue| queue.pop_front()),
+            None => None,
+        };
+        let animation = match next {
+            Some(next) => {
+                *anim_handle = next;
+                player.reset();
+                match animations.get(&*anim_handle) {
+                    Some(anim) => anim,
+                    None => continue,
+                }
+            }
+            None => animation,
+        };
+
+        // Show the atlas the animation's frames index into, which changes when switching to an
+        // animation from another sheet.
+        if let Some(sprite_atlas) = sprite_atlases.get(&animation.atlas) {
+            if *texture_atlas != sprite_atlas.atlas {
+                *texture_atlas = sprite_atlas.atlas.clone();
+            }
+        }
 
         // Update the sprite's index into the texture atlas.
-        texture.index = player.frame_index();
+        texture.index = player.sprite_index(animation);
+    }
+}
+
+fn log_animation_events(
+    mut animation_events: EventReader<AnimationEvent>,
+) {
+    for event in animation_events.read() {
+        trace!("{:?} reached animation event {}", event.entity, event.name);
+    }
+}
+
+fn log_finished_animations(
+    mut finished_events: EventReader<AnimationFinished>,
+) {
+    for event in finished_events.read() {
+        trace!("{:?} finished animation {:?}", event.entity, event.animation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tagged_frames(events: &[&str], mode: LoopMode) -> Animation {
+        let frames = events.iter().enumerate().map(|(index, event)| Frame {
+            index,
+            duration: Duration::from_millis(100),
+            events: vec![event.to_string()],
+        });
+        Animation::from_frames(frames, mode, Handle::default())
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn update(state: &mut AnimationState, animation: &Animation, millis: u64) -> (Vec<String>, bool) {
+        let mut events = Vec::new();
+        let completed = state.update(animation, Duration::from_millis(millis), |frame| {
+            events.extend(frame.events.iter().cloned());
+        });
+        (events, completed)
+    }
+
+    #[test]
+    fn events_fire_for_every_frame_skipped_in_one_update() {
+        let animation = tagged_frames(&["a", "b", "c", "d"], LoopMode::Repeat);
+        let mut state = AnimationState::default();
+
+        assert_eq!(update(&mut state, &animation, 0), (names(&["a"]), false));
+        assert_eq!(update(&mut state, &animation, 350), (names(&["b", "c", "d"]), false));
+        assert_eq!(state.sprite_index(&animation), 3);
+    }
+
+    #[test]
+    fn events_fire_in_order_when_wrapping_around() {
+        let animation = tagged_frames(&["a", "b", "c"], LoopMode::Repeat);
+        let mut state = AnimationState::default();
+
+        assert_eq!(update(&mut state, &animation, 450), (names(&["a", "b", "c", "a", "b"]), true));
+        assert_eq!(state.sprite_index(&animation), 1);
+    }
+
+    #[test]
+    fn animations_that_play_once_stop_on_their_last_frame() {
+        let animation = tagged_frames(&["a", "b", "c"], LoopMode::Once);
+        let mut state = AnimationState::default();
+
+        assert_eq!(update(&mut state, &animation, 1000), (names(&["a", "b", "c"]), true));
+        assert!(state.is_ended());
+        assert_eq!(update(&mut state, &animation, 1000), (names(&[]), false));
+        assert_eq!(state.sprite_index(&animation), 2);
+    }
+
+    fn parse(ron: &str) -> Result<Animation, AnimationLoaderError> {
+        let file: AnimationFile = ron::de::from_str(ron)?;
+        file.into_animation(Handle::default())
+    }
+
+    #[test]
+    fn ping_pong_animations_turn_around_without_repeating_the_ends() {
+        let animation = parse("(
+            atlas: \"bird.atlas.ron\",
+            mode: PingPong,
+            frame_ms: 100,
+            frames: [(index: 4), (index: 5), (index: 6)],
+        )").unwrap();
+        let mut state = AnimationState::default();
+
+        let mut indices = Vec::new();
+        for _ in 0..6 {
+            update(&mut state, &animation, 100);
+            indices.push(state.sprite_index(&animation));
+        }
+        assert_eq!(indices, [5, 6, 5, 4, 5, 6]);
+    }
+
+    #[test]
+    fn frames_can_override_the_frame_duration() {
+        let animation = parse("(
+            atlas: \"bird.atlas.ron\",
+            frame_ms: 100,
+            frames: [(index: 0, ms: Some(300), events: [\"flap\"]), (index: 1)],
+        )").unwrap();
+        let mut state = AnimationState::default();
+
+        assert_eq!(update(&mut state, &animation, 250), (names(&["flap"]), false));
+        assert_eq!(state.sprite_index(&animation), 0);
+        assert_eq!(update(&mut state, &animation, 100), (names(&[]), false));
+        assert_eq!(state.sprite_index(&animation), 1);
+    }
+
+    #[test]
+    fn animations_without_frames_are_rejected() {
+        let result = parse("(atlas: \"bird.atlas.ron\", frame_ms: 100, frames: [])");
+        assert!(matches!(result, Err(AnimationLoaderError::NoFrames)));
     }
 }
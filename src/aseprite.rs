@@ -0,0 +1,266 @@
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use bevy::prelude::*;
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    math::Rect,
+    reflect::TypePath,
+    utils::BoxedFuture,
+};
+use serde::{de, Deserialize, Deserializer};
+use thiserror::Error;
+
+use crate::{
+    animation::{Animation, Frame, LoopMode},
+    assets,
+    atlas::SpriteAtlas,
+};
+
+/// Labels of the sub-assets every sheet adds. Tags can't use these names.
+const TEXTURE_ATLAS_LABEL: &str = "texture_atlas";
+const ATLAS_LABEL: &str = "atlas";
+
+#[derive(Debug, Deserialize)]
+struct AseRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+impl AseRect {
+    fn to_rect(&self) -> Rect {
+        Rect::new(self.x as f32, self.y as f32, (self.x + self.w) as f32, (self.y + self.h) as f32)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AseSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct AseFrame {
+    frame: AseRect,
+    /// In milliseconds.
+    duration: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AseDirection {
+    #[default]
+    Forward,
+    Reverse,
+    Pingpong,
+    PingpongReverse,
+}
+
+#[derive(Debug, Deserialize)]
+struct AseTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: AseDirection,
+    /// How many times to play the tag. Loops forever if missing.
+    #[serde(default)]
+    repeat: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AseSliceKey {
+    bounds: AseRect,
+}
+
+#[derive(Debug, Deserialize)]
+struct AseSlice {
+    name: String,
+    keys: Vec<AseSliceKey>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AseCel {
+    frame: usize,
+    #[serde(default)]
+    data: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AseLayer {
+    /// Only cels with user data are exported.
+    #[serde(default)]
+    cels: Vec<AseCel>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AseMeta {
+    /// Path to the sprite sheet image, relative to the JSON file.
+    image: String,
+    size: AseSize,
+    #[serde(default)]
+    frame_tags: Vec<AseTag>,
+    #[serde(default)]
+    slices: Vec<AseSlice>,
+    #[serde(default)]
+    layers: Vec<AseLayer>,
+}
+
+/// The contents of a JSON file exported by Aseprite, in either the hash or the array layout.
+#[derive(Debug, Deserialize)]
+struct AseFile {
+    #[serde(deserialize_with = "frames_in_order")]
+    frames: Vec<AseFrame>,
+    meta: AseMeta,
+}
+
+/// Reads frames from either an array or a map of file names to frames, keeping the file's order.
+fn frames_in_order<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<AseFrame>, D::Error> {
+    struct FramesVisitor;
+
+    impl<'de> de::Visitor<'de> for FramesVisitor {
+        type Value = Vec<AseFrame>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an array or map of frames")
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut frames = Vec::new();
+            while let Some(frame) = seq.next_element()? {
+                frames.push(frame);
+            }
+            Ok(frames)
+        }
+
+        fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut frames = Vec::new();
+            while let Some((_, frame)) = map.next_entry::<de::IgnoredAny, _>()? {
+                frames.push(frame);
+            }
+            Ok(frames)
+        }
+    }
+
+    deserializer.deserialize_any(FramesVisitor)
+}
+
+/// A sprite sheet exported from Aseprite, loaded from a `.aseprite.json` file.
+///
+/// Each tag is also available as an `Animation` labeled with the tag's name, so `bird.aseprite.json#flap`
+/// loads the `flap` tag. Slices become named regions of the atlas, using their bounds on the first key.
+/// Cel user data names the animation events sent on that cel's frame, separated by commas.
+#[derive(Asset, TypePath)]
+pub struct AsepriteSheet {
+    pub atlas: Handle<SpriteAtlas>,
+    pub animations: BTreeMap<String, Handle<Animation>>,
+}
+
+#[derive(Debug, Error)]
+pub enum AsepriteLoaderError {
+    #[error("Could not read asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Tag {0} has frames that don't exist")]
+    BadTag(String),
+    #[error("Tag name {0} is reserved")]
+    ReservedTag(String),
+}
+
+#[derive(Default)]
+pub struct AsepriteLoader;
+
+impl AssetLoader for AsepriteLoader {
+    type Asset = AsepriteSheet;
+    type Settings = ();
+    type Error = AsepriteLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let file: AseFile = serde_json::from_slice(&bytes)?;
+
+            let image = load_context.load(assets::relative_path(load_context, &file.meta.image));
+            let size = Vec2::new(file.meta.size.w as f32, file.meta.size.h as f32);
+            let mut texture_atlas = TextureAtlas::new_empty(image, size);
+            for frame in &file.frames {
+                texture_atlas.add_texture(frame.frame.to_rect());
+            }
+            let regions = file.meta.slices.iter()
+                .filter_map(|slice| {
+                    let key = slice.keys.first()?;
+                    Some((slice.name.clone(), texture_atlas.add_texture(key.bounds.to_rect())))
+                })
+                .collect();
+
+            let texture_atlas = load_context.add_labeled_asset(TEXTURE_ATLAS_LABEL.into(), texture_atlas);
+            let atlas = load_context.add_labeled_asset(ATLAS_LABEL.into(), SpriteAtlas::new(texture_atlas, regions));
+
+            let mut frame_events = vec![Vec::new(); file.frames.len()];
+            for cel in file.meta.layers.iter().flat_map(|layer| &layer.cels) {
+                let Some(events) = frame_events.get_mut(cel.frame) else {
+                    continue;
+                };
+                events.extend(cel.data.split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(String::from));
+            }
+
+            let mut animations = BTreeMap::new();
+            for tag in &file.meta.frame_tags {
+                if [TEXTURE_ATLAS_LABEL, ATLAS_LABEL].contains(&tag.name.as_str()) {
+                    return Err(AsepriteLoaderError::ReservedTag(tag.name.clone()));
+                }
+                if tag.from > tag.to || tag.to >= file.frames.len() {
+                    return Err(AsepriteLoaderError::BadTag(tag.name.clone()));
+                }
+
+                let (frames, mode) = tag_frames(tag);
+                let frames = frames.into_iter()
+                    .map(|index| Frame {
+                        index,
+                        duration: Duration::from_millis(file.frames[index].duration),
+                        events: frame_events[index].clone(),
+                    });
+                let animation = Animation::from_frames(frames, mode, atlas.clone());
+                animations.insert(tag.name.clone(), load_context.add_labeled_asset(tag.name.clone(), animation));
+            }
+
+            Ok(AsepriteSheet { atlas, animations })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["aseprite.json"]
+    }
+}
+
+/// Lists the frames played by one cycle of a tag, repeated as many times as the tag asks for.
+fn tag_frames(tag: &AseTag) -> (Vec<usize>, LoopMode) {
+    let forward: Vec<usize> = (tag.from..=tag.to).collect();
+    let backward: Vec<usize> = forward.iter().rev().copied().collect();
+    // Ping-pong cycles don't repeat the frames they turn around on.
+    let inner = |frames: &[usize]| frames[1..frames.len().saturating_sub(1).max(1)].to_vec();
+
+    let cycle = match tag.direction {
+        AseDirection::Forward => forward,
+        AseDirection::Reverse => backward,
+        AseDirection::Pingpong => [forward, inner(&backward)].concat(),
+        AseDirection::PingpongReverse => [backward, inner(&forward)].concat(),
+    };
+
+    match tag.repeat.as_deref().and_then(|repeat| repeat.parse::<usize>().ok()) {
+        Some(times) if times > 0 => (cycle.repeat(times), LoopMode::Once),
+        _ => (cycle, LoopMode::Repeat),
+    }
+}
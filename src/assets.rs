@@ -1,15 +1,26 @@
-use std::time::Duration;
+use std::{
+    marker::PhantomData,
+    path::{Component, Path, PathBuf},
+};
 
 use bevy::prelude::*;
 use bevy::{
-    math::Rect,
-    render::texture::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor},
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    utils::BoxedFuture,
 };
 use bevy_asset_loader::prelude::*;
+use serde::Deserialize;
+use thiserror::Error;
 
 use crate::{
     AppState,
-    animation::Animation,
+    achievements::AchievementList,
+    aseprite::{AsepriteLoader, AsepriteSheet},
+    atlas::{SpriteAtlas, SpriteAtlasLoader},
+    daynight::DayNightCycle,
+    particles::{ParticlePresets, ParticlePresetsLoader},
+    skins::{BirdSkin, SkinAnimations},
+    themes::{ThemeList, ThemeListLoader},
 };
 
 pub struct AssetsPlugin;
@@ -17,6 +28,18 @@ pub struct AssetsPlugin;
 impl Plugin for AssetsPlugin {
     fn build(&self, app: &mut App) {
         app
+            .init_asset::<AchievementList>()
+            .register_asset_loader(RonAssetLoader::<AchievementList>::new(&["achievements.ron"]))
+            .init_asset::<SpriteAtlas>()
+            .register_asset_loader(SpriteAtlasLoader)
+            .init_asset::<AsepriteSheet>()
+            .register_asset_loader(AsepriteLoader)
+            .init_asset::<ThemeList>()
+            .register_asset_loader(ThemeListLoader)
+            .init_asset::<DayNightCycle>()
+            .register_asset_loader(RonAssetLoader::<DayNightCycle>::new(&["daynight.ron"]))
+            .init_asset::<ParticlePresets>()
+            .register_asset_loader(ParticlePresetsLoader)
             .add_loading_state(
                 LoadingState::new(AppState::Loading)
                     .continue_to_state(AppState::MainMenu)
@@ -31,95 +54,120 @@ pub struct GameAssets {
     #[asset(path = "fonts/Kenney Blocks.ttf")]
     pub font: Handle<Font>,
 
-    #[asset(texture_atlas(tile_size_x = 16.0, tile_size_y = 16.0, columns = 4, rows = 1))]
-    #[asset(path = "sprites/Player/bird1.png")]
-    pub bird_atlas: Handle<TextureAtlas>,
-    pub bird_anim: Handle<Animation>,
-
-    #[asset(path = "sprites/Background/Background5.png")]
-    pub background: Handle<Image>,
-
-    #[asset(path = "sprites/Tileset/Style 1/OldS2.png")]
-    pub terrain_image: Handle<Image>,
-    pub terrain_atlas: Handle<TextureAtlas>,
-    pub terrain_indices: TerrainAtlasIndices,
-
-    #[asset(path = "sprites/Tileset/Style 1/OldS2-PipeCenter.png")]
-    pub pipe_center: Handle<Image>,
-    #[asset(path = "sprites/Tileset/Style 1/OldS2-GroundTop.png")]
-    pub ground_top: Handle<Image>,
-    #[asset(path = "sprites/Tileset/Style 1/OldS2-Ground.png")]
-    pub ground: Handle<Image>,
+    #[asset(path = "data/game.achievements.ron")]
+    pub achievements: Handle<AchievementList>,
+
+    #[asset(path = "data/game.themes.ron")]
+    pub themes: Handle<ThemeList>,
+
+    #[asset(path = "data/game.daynight.ron")]
+    pub day_night: Handle<DayNightCycle>,
+
+    #[asset(path = "data/game.particles.ron")]
+    pub particles: Handle<ParticlePresets>,
+
+    #[asset(path = "sprites/Player/bird1.aseprite.json")]
+    pub bird_yellow_sheet: Handle<AsepriteSheet>,
+    /// Filled in from the sheet once loaded.
+    pub bird_yellow: SkinAnimations,
+    #[asset(path = "sprites/Player/bird2.aseprite.json")]
+    pub bird_blue_sheet: Handle<AsepriteSheet>,
+    /// Filled in from the sheet once loaded.
+    pub bird_blue: SkinAnimations,
+    #[asset(path = "sprites/Player/bird3.aseprite.json")]
+    pub bird_red_sheet: Handle<AsepriteSheet>,
+    /// Filled in from the sheet once loaded.
+    pub bird_red: SkinAnimations,
 }
 
-#[derive(Default)]
-pub struct TerrainAtlasIndices {
-    pub pipe_top: usize,
-    pub pipe_bottom: usize,
-    pub pipe_center: usize,
+/// Loads any deserializable asset type from a RON file with one of the given extensions.
+pub struct RonAssetLoader<A> {
+    extensions: Vec<&'static str>,
+    _marker: PhantomData<fn() -> A>,
+}
 
-    pub ground_top: usize,
-    pub ground: usize,
+impl<A> RonAssetLoader<A> {
+    pub fn new(extensions: &[&'static str]) -> Self {
+        Self {
+            extensions: extensions.to_vec(),
+            _marker: PhantomData,
+        }
+    }
 }
 
-fn assets_loaded(
-    mut assets: ResMut<GameAssets>,
-    mut animations: ResMut<Assets<Animation>>,
-    mut atlases: ResMut<Assets<TextureAtlas>>,
-    mut images: ResMut<Assets<Image>>,
-) {
-    debug!("Loaded assets!");
+#[derive(Debug, Error)]
+pub enum RonAssetLoaderError {
+    #[error("Could not read asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
 
-    // Bird anim info asset.
-    let bird_anim = Animation::from_indices(0..=3, Duration::from_millis(150));
-    assets.bird_anim = animations.add(bird_anim);
-
-    // Populate terrain texture atlas.
-    if let Some(image) = images.get_mut(&assets.terrain_image) {
-        image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
-            address_mode_u: ImageAddressMode::Repeat,
-            address_mode_v: ImageAddressMode::Repeat,
-            ..default()
-        });
-
-        let mut atlas = TextureAtlas::new_empty(assets.terrain_image.clone(), image.size().as_vec2());
-        assets.terrain_indices.pipe_bottom = atlas.add_texture(Rect {
-            min: Vec2::new(0.0, 0.0),
-            max: Vec2::new(32.0, 16.0),
-        });
-        assets.terrain_indices.pipe_top = atlas.add_texture(Rect {
-            min: Vec2::new(0.0, 64.0),
-            max: Vec2::new(32.0, 80.0),
-        });
-        assets.terrain_indices.pipe_center = atlas.add_texture(Rect {
-            min: Vec2::new(2.0, 32.0),
-            max: Vec2::new(30.0, 48.0),
-        });
-        assets.terrain_indices.ground_top = atlas.add_texture(Rect {
-            min: Vec2::new(0.0, 80.0),
-            max: Vec2::new(16.0, 96.0),
-        });
-        assets.terrain_indices.ground = atlas.add_texture(Rect {
-            min: Vec2::new(0.0, 96.0),
-            max: Vec2::new(16.0, 112.0),
-        });
-
-        assets.terrain_atlas = atlases.add(atlas);
+impl<A> AssetLoader for RonAssetLoader<A>
+where
+    A: Asset + for<'de> Deserialize<'de>,
+{
+    type Asset = A;
+    type Settings = ();
+    type Error = RonAssetLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(ron::de::from_bytes(&bytes)?)
+        })
     }
 
-    // Set repeat address mode on tiling textures.
-    if let Some(image) = images.get_mut(&assets.ground) {
-        image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
-            address_mode_u: ImageAddressMode::Repeat,
-            address_mode_v: ImageAddressMode::Repeat,
-            ..default()
-        });
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
     }
-    if let Some(image) = images.get_mut(&assets.ground_top) {
-        image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
-            address_mode_u: ImageAddressMode::Repeat,
-            address_mode_v: ImageAddressMode::Repeat,
-            ..default()
-        });
+}
+
+/// Resolves a path found in an asset file relative to the directory that file is in. `..` steps
+/// are resolved so the same file always gets the same asset path.
+pub fn relative_path(load_context: &LoadContext, path: &str) -> PathBuf {
+    let dir = load_context.path().parent().unwrap_or(Path::new(""));
+    let mut resolved = PathBuf::new();
+    for component in dir.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
     }
+    resolved
+}
+
+fn assets_loaded(
+    mut assets: ResMut<GameAssets>,
+    sheets: Res<Assets<AsepriteSheet>>,
+    sprite_atlases: Res<Assets<SpriteAtlas>>,
+) {
+    debug!("Loaded assets!");
+
+    // Bird animations come from the tags in each skin's sprite sheet.
+    let skin_animations = |skin: BirdSkin| {
+        let Some(sheet) = sheets.get(skin.sheet(&assets)) else {
+            error!("Sprite sheet for the {} bird isn't loaded", skin.name());
+            return SkinAnimations::default();
+        };
+        let atlas = sprite_atlases.get(&sheet.atlas)
+            .map(|sprite_atlas| sprite_atlas.atlas.clone())
+            .unwrap_or_default();
+        SkinAnimations::from_sheet(sheet, atlas)
+    };
+    let yellow = skin_animations(BirdSkin::Yellow);
+    let blue = skin_animations(BirdSkin::Blue);
+    let red = skin_animations(BirdSkin::Red);
+    assets.bird_yellow = yellow;
+    assets.bird_blue = blue;
+    assets.bird_red = red;
 }
@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadDirectError},
+    math::Rect,
+    reflect::TypePath,
+    render::{
+        render_resource::{Extent3d, TextureDimension},
+        texture::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor, TextureFormatPixelInfo},
+    },
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::assets;
+
+/// A rectangle of pixels within an image, as `(min_x, min_y, max_x, max_y)`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct PixelRect(pub u32, pub u32, pub u32, pub u32);
+
+impl PixelRect {
+    fn width(&self) -> u32 {
+        self.2.saturating_sub(self.0)
+    }
+
+    fn height(&self) -> u32 {
+        self.3.saturating_sub(self.1)
+    }
+
+    fn to_rect(self) -> Rect {
+        Rect::new(self.0 as f32, self.1 as f32, self.2 as f32, self.3 as f32)
+    }
+
+    fn fits_in(&self, size: UVec2) -> bool {
+        self.width() > 0 && self.height() > 0 && self.2 <= size.x && self.3 <= size.y
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+enum AddressMode {
+    #[default]
+    ClampToEdge,
+    Repeat,
+    MirrorRepeat,
+}
+
+impl From<AddressMode> for ImageAddressMode {
+    fn from(mode: AddressMode) -> Self {
+        match mode {
+            AddressMode::ClampToEdge => Self::ClampToEdge,
+            AddressMode::Repeat => Self::Repeat,
+            AddressMode::MirrorRepeat => Self::MirrorRepeat,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+enum FilterMode {
+    #[default]
+    Nearest,
+    Linear,
+}
+
+impl From<FilterMode> for ImageFilterMode {
+    fn from(mode: FilterMode) -> Self {
+        match mode {
+            FilterMode::Nearest => Self::Nearest,
+            FilterMode::Linear => Self::Linear,
+        }
+    }
+}
+
+/// Evenly sized cells laid out in rows, indexed left to right then top to bottom.
+#[derive(Debug, Deserialize)]
+struct GridLayout {
+    tile_size: (u32, u32),
+    columns: usize,
+    rows: usize,
+    #[serde(default)]
+    padding: (u32, u32),
+    #[serde(default)]
+    offset: (u32, u32),
+}
+
+/// The contents of a `.atlas.ron` file.
+#[derive(Debug, Deserialize)]
+struct AtlasManifest {
+    /// Path to the image, relative to the manifest.
+    image: String,
+    #[serde(default)]
+    address_mode: AddressMode,
+    #[serde(default)]
+    filter: FilterMode,
+    /// Grid cells come first in the atlas, before any named regions.
+    #[serde(default)]
+    grid: Option<GridLayout>,
+    #[serde(default)]
+    regions: BTreeMap<String, PixelRect>,
+    /// Named regions to also copy into their own repeating images, so they can be tiled.
+    #[serde(default)]
+    tiles: Vec<String>,
+}
+
+/// A texture atlas with named regions, loaded from a `.atlas.ron` manifest.
+#[derive(Asset, TypePath)]
+pub struct SpriteAtlas {
+    pub atlas: Handle<TextureAtlas>,
+    regions: BTreeMap<String, usize>,
+    tiles: BTreeMap<String, Handle<Image>>,
+}
+
+impl SpriteAtlas {
+    pub fn new(atlas: Handle<TextureAtlas>, regions: BTreeMap<String, usize>) -> Self {
+        Self {
+            atlas,
+            regions,
+            tiles: default(),
+        }
+    }
+
+    /// Index into `atlas` of a named region.
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.regions.get(name).copied()
+    }
+
+    /// Repeating image cut out of a named region listed in the manifest's `tiles`.
+    pub fn tile(&self, name: &str) -> Option<Handle<Image>> {
+        self.tiles.get(name).cloned()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SpriteAtlasLoaderError {
+    #[error("Could not read asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("Could not load atlas image: {0}")]
+    LoadImage(#[from] LoadDirectError),
+    #[error("Atlas image {0} is not an image")]
+    NotAnImage(String),
+    #[error("Region {0} is empty or outside the atlas image")]
+    BadRegion(String),
+    #[error("Tile {0} is not a named region")]
+    UnknownTile(String),
+}
+
+#[derive(Default)]
+pub struct SpriteAtlasLoader;
+
+impl AssetLoader for SpriteAtlasLoader {
+    type Asset = SpriteAtlas;
+    type Settings = ();
+    type Error = SpriteAtlasLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let manifest: AtlasManifest = ron::de::from_bytes(&bytes)?;
+
+            let image_path = assets::relative_path(load_context, &manifest.image);
+            let mut image = load_context.load_direct(image_path).await?
+                .take::<Image>()
+                .ok_or_else(|| SpriteAtlasLoaderError::NotAnImage(manifest.image.clone()))?;
+            let size = image.size();
+
+            for (name, rect) in &manifest.regions {
+                if !rect.fits_in(size) {
+                    return Err(SpriteAtlasLoaderError::BadRegion(name.clone()));
+                }
+            }
+
+            let mut tiles = BTreeMap::new();
+            for name in &manifest.tiles {
+                let rect = manifest.regions.get(name)
+                    .ok_or_else(|| SpriteAtlasLoaderError::UnknownTile(name.clone()))?;
+                let tile = extract_tile(&image, *rect, manifest.filter.into());
+                tiles.insert(name.clone(), load_context.add_labeled_asset(format!("tile/{}", name), tile));
+            }
+
+            let address_mode = manifest.address_mode.into();
+            let filter = manifest.filter.into();
+            image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+                address_mode_u: address_mode,
+                address_mode_v: address_mode,
+                mag_filter: filter,
+                min_filter: filter,
+                ..default()
+            });
+            let texture = load_context.add_labeled_asset("image".into(), image);
+
+            let mut atlas = match &manifest.grid {
+                Some(grid) => TextureAtlas::from_grid(
+                    texture,
+                    Vec2::new(grid.tile_size.0 as f32, grid.tile_size.1 as f32),
+                    grid.columns,
+                    grid.rows,
+                    Some(Vec2::new(grid.padding.0 as f32, grid.padding.1 as f32)),
+                    Some(Vec2::new(grid.offset.0 as f32, grid.offset.1 as f32)),
+                ),
+                None => TextureAtlas::new_empty(texture, size.as_vec2()),
+            };
+            let regions = manifest.regions.iter()
+                .map(|(name, rect)| (name.clone(), atlas.add_texture(rect.to_rect())))
+                .collect();
+
+            Ok(SpriteAtlas {
+                atlas: load_context.add_labeled_asset("atlas".into(), atlas),
+                regions,
+                tiles,
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["atlas.ron"]
+    }
+}
+
+/// Copies a region of an image into a new image that repeats, so the region can be tiled.
+fn extract_tile(image: &Image, region: PixelRect, filter: ImageFilterMode) -> Image {
+    let pixel_size = image.texture_descriptor.format.pixel_size();
+    let image_width = image.size().x as usize;
+    let row_len = region.width() as usize * pixel_size;
+
+    let mut data = Vec::with_capacity(row_len * region.height() as usize);
+    for y in region.1..region.3 {
+        let start = (y as usize * image_width + region.0 as usize) * pixel_size;
+        data.extend_from_slice(&image.data[start..start + row_len]);
+    }
+
+    let size = Extent3d {
+        width: region.width(),
+        height: region.height(),
+        depth_or_array_layers: 1,
+    };
+    let mut tile = Image::new(size, TextureDimension::D2, data, image.texture_descriptor.format);
+    tile.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        mag_filter: filter,
+        min_filter: filter,
+        ..default()
+    });
+    tile
+}
@@ -1,7 +1,27 @@
 use bevy::prelude::*;
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages},
+        texture::{BevyDefault, ImageSampler},
+        view::RenderLayers,
+    },
+    window::PrimaryWindow,
+};
 use bevy_egui::EguiContexts;
 use noise::{NoiseFn, Perlin};
 
+use crate::{GAME_SIZE, window};
+
+/// Extra pixels drawn around each edge of the game view so it can be nudged by less than a pixel
+/// without showing its edges.
+const VIEW_MARGIN: f32 = 1.0;
+/// Render layer of the screen that shows the game view, so the world camera doesn't draw it.
+const SCREEN_LAYER: u8 = 1;
+/// Widest the play field gets, enough to fill a super ultrawide display.
+const MAX_PLAY_WIDTH: f32 = 1140.0;
+
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
@@ -9,7 +29,11 @@ impl Plugin for CameraPlugin {
         app
             .register_type::<CameraShake>()
             .init_resource::<StoredNoise>()
-            .add_systems(Update, update_camera_shake);
+            .init_resource::<GameView>()
+            .init_resource::<PlayField>()
+            .add_systems(PreUpdate, update_play_field)
+            .add_systems(Update, update_camera_shake)
+            .add_systems(PostUpdate, (fit_game_view, update_screen));
 
         if cfg!(debug_assertions) {
             app.add_systems(Update, check_camera_shake_input.before(update_camera_shake));
@@ -34,6 +58,125 @@ impl Default for StoredNoise {
     }
 }
 
+/// Size of the part of the world that's shown. The height is fixed, while the width grows to fill
+/// wide windows.
+#[derive(PartialEq, Resource)]
+pub struct PlayField {
+    pub width: f32,
+}
+
+impl Default for PlayField {
+    fn default() -> Self {
+        Self {
+            width: GAME_SIZE.0,
+        }
+    }
+}
+
+impl PlayField {
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width, GAME_SIZE.1)
+    }
+}
+
+/// Part of the world a camera shows, ignoring any rotation.
+pub fn visible_rect(projection: &OrthographicProjection, transform: &GlobalTransform) -> Rect {
+    let offset = transform.translation().truncate();
+    Rect::from_corners(projection.area.min + offset, projection.area.max + offset)
+}
+
+/// The image the world is rendered into at its native resolution, before being scaled up to the
+/// window by a whole number.
+#[derive(Resource)]
+pub struct GameView {
+    image: Handle<Image>,
+    /// Part of the camera's offset that's smaller than a pixel, applied when showing the view.
+    subpixel: Vec2,
+}
+
+impl FromWorld for GameView {
+    fn from_world(world: &mut World) -> Self {
+        let size = Extent3d {
+            width: (GAME_SIZE.0 + VIEW_MARGIN * 2.0) as u32,
+            height: (GAME_SIZE.1 + VIEW_MARGIN * 2.0) as u32,
+            depth_or_array_layers: 1,
+        };
+        let mut image = Image {
+            texture_descriptor: TextureDescriptor {
+                label: Some("game_view"),
+                size,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::bevy_default(),
+                mip_level_count: 1,
+                sample_count: 1,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            },
+            sampler: ImageSampler::nearest(),
+            ..default()
+        };
+        // Fill the image with zeroes.
+        image.resize(size);
+
+        Self {
+            image: world.resource_mut::<Assets<Image>>().add(image),
+            subpixel: Vec2::ZERO,
+        }
+    }
+}
+
+impl GameView {
+    /// A camera that draws the world into the game view. The camera's origin is the bottom left of
+    /// the play field, with values increasing up and to the right.
+    pub fn world_camera(&self) -> (Camera2dBundle, WorldCamera) {
+        let mut camera_bundle = Camera2dBundle::default();
+        camera_bundle.camera.target = RenderTarget::Image(self.image.clone());
+        (camera_bundle, WorldCamera)
+    }
+
+    /// Spawns a camera that shows the game view centered in the window, with black bars filling
+    /// any space left over. Returns the camera.
+    pub fn spawn_screen(&self, commands: &mut Commands) -> Entity {
+        let sprite = commands.spawn(SpriteBundle {
+                texture: self.image.clone(),
+                ..default()
+            })
+            .insert(Name::new("Screen Sprite"))
+            .insert(ScreenSprite)
+            .insert(RenderLayers::layer(SCREEN_LAYER))
+            .id();
+
+        let camera_bundle = Camera2dBundle {
+            camera: Camera {
+                // Draw after the world camera has filled in the game view.
+                order: 1,
+                ..default()
+            },
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::Custom(Color::BLACK),
+            },
+            ..default()
+        };
+        commands.spawn(camera_bundle)
+            .insert(ScreenCamera)
+            .insert(RenderLayers::layer(SCREEN_LAYER))
+            .add_child(sprite)
+            .id()
+    }
+}
+
+/// Camera drawing the world into the game view.
+#[derive(Component)]
+pub struct WorldCamera;
+
+/// Camera drawing the screen in the window.
+#[derive(Component)]
+struct ScreenCamera;
+
+/// Sprite showing the game view on screen.
+#[derive(Component)]
+struct ScreenSprite;
+
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct CameraShake {
@@ -76,6 +219,7 @@ impl CameraShake {
 fn update_camera_shake(
     time: Res<Time>,
     stored_noise: Res<StoredNoise>,
+    mut game_view: ResMut<GameView>,
     mut camera_q: Query<(&mut Transform, &mut CameraShake)>,
 ) {
     let secs = time.elapsed_seconds_f64();
@@ -91,14 +235,21 @@ fn update_camera_shake(
 
             let offset_x_mult = stored_noise.offset_noise_x.get([0.0, secs * noise_scale]);
             let offset_y_mult = stored_noise.offset_noise_y.get([0.0, secs * noise_scale]);
-            transform.translation.x = shake.max_offset * shake_value * offset_x_mult as f32;
-            transform.translation.y = shake.max_offset * shake_value * offset_y_mult as f32;
+            let offset = Vec2::new(offset_x_mult as f32, offset_y_mult as f32) * shake.max_offset * shake_value;
+
+            // Only move the camera by whole pixels so the world stays on the pixel grid. The rest
+            // moves the screen, which can move by screen pixels.
+            let snapped = offset.round();
+            transform.translation.x = snapped.x;
+            transform.translation.y = snapped.y;
+            game_view.subpixel = offset - snapped;
 
             shake.decay_trauma(dt);
         } else {
             transform.translation.x = 0.0;
             transform.translation.y = 0.0;
             transform.rotation = Quat::IDENTITY;
+            game_view.subpixel = Vec2::ZERO;
         }
     }
 }
@@ -126,3 +277,83 @@ fn check_camera_shake_input(
         shake.add_trauma(trauma_to_add);
     }
 }
+
+/// Widens the play field to fill the window at the scale the game is shown at.
+fn update_play_field(
+    mut play_field: ResMut<PlayField>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Ok(window) = window_q.get_single() else {
+        return;
+    };
+
+    let scale = window::fit_scale(window) as f32;
+    let width = (window.physical_width() as f32 / scale).floor().clamp(GAME_SIZE.0, MAX_PLAY_WIDTH);
+    play_field.set_if_neq(PlayField { width });
+}
+
+/// Resizes the game view to the play field, and keeps the world camera's origin at the bottom left
+/// of it.
+fn fit_game_view(
+    play_field: Res<PlayField>,
+    game_view: Res<GameView>,
+    mut images: ResMut<Assets<Image>>,
+    mut camera_q: Query<(&mut OrthographicProjection, Ref<WorldCamera>)>,
+) {
+    let view_size = play_field.size() + VIEW_MARGIN * 2.0;
+    if play_field.is_changed() {
+        debug!("Play field is now {} wide", play_field.width);
+        if let Some(image) = images.get_mut(&game_view.image) {
+            image.resize(Extent3d {
+                width: view_size.x as u32,
+                height: view_size.y as u32,
+                depth_or_array_layers: 1,
+            });
+        }
+    }
+
+    for (mut projection, world_camera) in camera_q.iter_mut() {
+        if play_field.is_changed() || world_camera.is_added() {
+            // Leave the margin below and to the left of the origin.
+            projection.viewport_origin = Vec2::splat(VIEW_MARGIN) / view_size;
+        }
+    }
+}
+
+/// Scales the game view up to fit the window by a whole number, keeping it centered on whole
+/// pixels, and applies the camera's subpixel offset in steps of a screen pixel.
+fn update_screen(
+    game_view: Res<GameView>,
+    play_field: Res<PlayField>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut camera_q: Query<&mut OrthographicProjection, With<ScreenCamera>>,
+    mut sprite_q: Query<(&mut Sprite, &mut Transform), With<ScreenSprite>>,
+) {
+    let Ok(window) = window_q.get_single() else {
+        return;
+    };
+
+    // Work in physical pixels so the screen lines up with the display's pixels.
+    for mut projection in camera_q.iter_mut() {
+        projection.scale = window.scale_factor() as f32;
+    }
+
+    let scale = window::fit_scale(window) as f32;
+    let subpixel = (game_view.subpixel * scale).round() / scale;
+    // Image rows go down while the world goes up.
+    let min = Vec2::new(VIEW_MARGIN + subpixel.x, VIEW_MARGIN - subpixel.y);
+    let size = play_field.size();
+
+    let window_size = Vec2::new(window.physical_width() as f32, window.physical_height() as f32);
+    let screen_size = size * scale;
+    let bars = ((window_size - screen_size) / 2.0).floor();
+    let center = Vec2::new(
+        -window_size.x / 2.0 + bars.x + screen_size.x / 2.0,
+        window_size.y / 2.0 - bars.y - screen_size.y / 2.0,
+    );
+    for (mut sprite, mut transform) in sprite_q.iter_mut() {
+        sprite.custom_size = Some(screen_size);
+        sprite.rect = Some(Rect::from_corners(min, min + size));
+        transform.translation = center.extend(0.0);
+    }
+}
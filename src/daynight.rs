@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::reflect::TypePath;
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    assets::GameAssets,
+    game::{GameData, GameMode, GameState},
+    scrolling::ScrollingMaterial,
+    themes::{self, ActiveTheme, ThemePart, Themes},
+    tween::{Animator, Interpolate},
+};
+
+/// How quickly the shown time of day catches up when it jumps, like when scoring.
+const CATCH_UP_RATE: f32 = 3.0;
+
+pub struct DayNightPlugin;
+
+impl Plugin for DayNightPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<DayNightClock>()
+            .add_systems(OnEnter(GameState::Ready), reset_clock)
+            .add_systems(OnExit(AppState::InGame), reset_clear_color)
+            .add_systems(Update, update_clock
+                .run_if(in_state(AppState::InGame)))
+            .add_systems(PostUpdate, apply_day_night
+                .after(themes::apply_theme)
+                .run_if(in_state(AppState::InGame)));
+    }
+}
+
+#[derive(Debug, Deserialize)]
+enum CycleLength {
+    Points(u16),
+    Seconds(f32),
+}
+
+#[derive(Debug, Deserialize)]
+struct DayNightKeyframe {
+    name: String,
+    /// How far through the cycle this keyframe is, from 0 to 1.
+    at: f32,
+    /// sRGB tint multiplied into the world's colors.
+    tint: (u8, u8, u8),
+}
+
+/// Day/night cycle for each game mode, loaded from a `.daynight.ron` file.
+#[derive(Debug, Asset, TypePath, Deserialize)]
+pub struct DayNightCycle {
+    modes: BTreeMap<GameMode, CycleLength>,
+    keyframes: Vec<DayNightKeyframe>,
+}
+
+impl DayNightCycle {
+    /// Index of the keyframe at or before `position`, and how far it is towards the next one.
+    fn keyframe_at(&self, position: f32) -> Option<(usize, f32)> {
+        let last = self.keyframes.len().checked_sub(1)?;
+        let position = position.rem_euclid(1.0);
+        let index = self.keyframes.iter()
+            .rposition(|keyframe| keyframe.at <= position)
+            .unwrap_or(last);
+        let next = if index == last { 0 } else { index + 1 };
+
+        let span = (self.keyframes[next].at - self.keyframes[index].at).rem_euclid(1.0);
+        let into = (position - self.keyframes[index].at).rem_euclid(1.0);
+        let ratio = if span > 0.0 { into / span } else { 0.0 };
+        Some((index, ratio))
+    }
+
+    fn tint(&self, index: usize) -> Color {
+        let (r, g, b) = self.keyframes[index].tint;
+        Color::rgb_u8(r, g, b)
+    }
+}
+
+/// How far through the day/night cycle the current run is.
+#[derive(Default, Resource)]
+struct DayNightClock {
+    /// Time spent playing this run.
+    seconds: f32,
+    /// Cycles shown so far. Eases towards where the run actually is.
+    shown: f32,
+}
+
+fn reset_clock(
+    mut clock: ResMut<DayNightClock>,
+) {
+    *clock = DayNightClock::default();
+}
+
+fn update_clock(
+    time: Res<Time>,
+    game_state: Res<State<GameState>>,
+    game_mode: Res<GameMode>,
+    game_data: Res<GameData>,
+    assets: Res<GameAssets>,
+    cycles: Res<Assets<DayNightCycle>>,
+    mut clock: ResMut<DayNightClock>,
+) {
+    // Only the run moves time along, so it stands still while waiting to start or after crashing.
+    if *game_state.get() != GameState::Playing {
+        return;
+    }
+
+    clock.seconds += time.delta_seconds();
+    let Some(length) = cycles.get(&assets.day_night).and_then(|cycle| cycle.modes.get(&*game_mode)) else {
+        return;
+    };
+
+    let target = match *length {
+        CycleLength::Points(points) => game_data.score as f32 / points.max(1) as f32,
+        CycleLength::Seconds(seconds) => clock.seconds / seconds.max(f32::EPSILON),
+    };
+    let catch_up = (CATCH_UP_RATE * time.delta_seconds()).min(1.0);
+    clock.shown = f32::interpolate(clock.shown, target, catch_up);
+}
+
+fn tinted(color: Color, tint: Color) -> Color {
+    Color::rgba(color.r() * tint.r(), color.g() * tint.g(), color.b() * tint.b(), color.a())
+}
+
+/// The sky and the parts of the themed world that get tinted by the time of day.
+#[derive(SystemParam)]
+struct TintedParts<'w, 's> {
+    clear_color: ResMut<'w, ClearColor>,
+    scrolling_materials: ResMut<'w, Assets<ScrollingMaterial>>,
+    // Sprites that are fading in are left alone until they finish.
+    sprites: Query<'w, 's, &'static mut Sprite, (With<ThemePart>, Without<Animator<Sprite>>)>,
+    atlas_sprites: Query<'w, 's, &'static mut TextureAtlasSprite, With<ThemePart>>,
+    materials: Query<'w, 's, &'static Handle<ScrollingMaterial>, With<ThemePart>>,
+}
+
+fn apply_day_night(
+    assets: Res<GameAssets>,
+    cycles: Res<Assets<DayNightCycle>>,
+    clock: Res<DayNightClock>,
+    themes: Res<Themes>,
+    active_theme: Res<ActiveTheme>,
+    mut current_keyframe: Local<Option<usize>>,
+    mut parts: TintedParts,
+) {
+    let Some(cycle) = cycles.get(&assets.day_night) else {
+        return;
+    };
+    let Some((index, ratio)) = cycle.keyframe_at(clock.shown) else {
+        return;
+    };
+    if *current_keyframe != Some(index) {
+        *current_keyframe = Some(index);
+        debug!("Time of day is now {}", cycle.keyframes[index].name);
+    }
+
+    let next = (index + 1) % cycle.keyframes.len();
+    let tint = Color::interpolate(cycle.tint(index), cycle.tint(next), ratio);
+
+    if let Some(theme) = themes.get(active_theme.0) {
+        let sky = tinted(theme.clear_color, tint);
+        if parts.clear_color.0 != sky {
+            parts.clear_color.0 = sky;
+        }
+    }
+
+    // Only write colors that changed to keep change detection quiet.
+    for mut sprite in parts.sprites.iter_mut() {
+        if sprite.color != tint {
+            sprite.color = tint;
+        }
+    }
+    for mut sprite in parts.atlas_sprites.iter_mut() {
+        if sprite.color != tint {
+            sprite.color = tint;
+        }
+    }
+    for material_handle in parts.materials.iter() {
+        let needs_tint = parts.scrolling_materials.get(material_handle)
+            .is_some_and(|material| material.color != tint);
+        if needs_tint {
+            if let Some(material) = parts.scrolling_materials.get_mut(material_handle) {
+                material.color = tint;
+            }
+        }
+    }
+}
+
+/// Puts the sky back to the theme's own color for the menus.
+fn reset_clear_color(
+    themes: Res<Themes>,
+    active_theme: Res<ActiveTheme>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    if let Some(theme) = themes.get(active_theme.0) {
+        clear_color.0 = theme.clear_color;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle(keyframes: &[f32]) -> DayNightCycle {
+        DayNightCycle {
+            modes: BTreeMap::new(),
+            keyframes: keyframes.iter()
+                .map(|&at| DayNightKeyframe { name: at.to_string(), at, tint: (255, 255, 255) })
+                .collect(),
+        }
+    }
+
+    fn assert_keyframe(actual: Option<(usize, f32)>, index: usize, ratio: f32) {
+        let (actual_index, actual_ratio) = actual.expect("expected a keyframe");
+        assert_eq!(actual_index, index);
+        assert!((actual_ratio - ratio).abs() < 1e-4, "expected ratio {}, got {}", ratio, actual_ratio);
+    }
+
+    #[test]
+    fn positions_on_a_keyframe_start_it() {
+        let cycle = cycle(&[0.1, 0.5, 0.8]);
+        assert_keyframe(cycle.keyframe_at(0.5), 1, 0.0);
+        assert_keyframe(cycle.keyframe_at(0.1), 0, 0.0);
+    }
+
+    #[test]
+    fn positions_between_keyframes_blend_towards_the_next() {
+        let cycle = cycle(&[0.1, 0.5, 0.8]);
+        assert_keyframe(cycle.keyframe_at(0.3), 0, 0.5);
+    }
+
+    #[test]
+    fn positions_past_the_last_keyframe_blend_towards_the_first() {
+        let cycle = cycle(&[0.1, 0.5, 0.8]);
+        assert_keyframe(cycle.keyframe_at(0.9), 2, 1.0 / 3.0);
+    }
+
+    #[test]
+    fn positions_before_the_first_keyframe_wrap_back_to_the_last() {
+        let cycle = cycle(&[0.1, 0.5, 0.8]);
+        assert_keyframe(cycle.keyframe_at(0.05), 2, 0.25 / 0.3);
+    }
+
+    #[test]
+    fn positions_wrap_every_cycle() {
+        let cycle = cycle(&[0.1, 0.5, 0.8]);
+        assert_keyframe(cycle.keyframe_at(2.3), 0, 0.5);
+    }
+
+    #[test]
+    fn a_single_keyframe_never_blends() {
+        let cycle = cycle(&[0.4]);
+        assert_keyframe(cycle.keyframe_at(0.9), 0, 0.0);
+    }
+
+    #[test]
+    fn no_keyframes_means_no_keyframe() {
+        assert!(cycle(&[]).keyframe_at(0.5).is_none());
+    }
+}
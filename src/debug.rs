@@ -3,6 +3,8 @@ use bevy_egui::{egui, EguiContexts};
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_rapier2d::render::*;
 
+use crate::animation::AnimationState;
+
 pub struct DebugPlugin;
 
 impl Plugin for DebugPlugin {
@@ -12,6 +14,7 @@ impl Plugin for DebugPlugin {
             .add_plugins(RapierDebugRenderPlugin::default().disabled())
             .insert_resource(DebugUi::default())
             .add_systems(Update, debug_ui.run_if(debug_ui_enabled))
+            .add_systems(Update, animation_controls_ui.run_if(show_animation_controls).after(debug_ui))
             .add_systems(Update, toggle_debug_ui);
     }
 }
@@ -20,6 +23,9 @@ impl Plugin for DebugPlugin {
 struct DebugUi {
     enabled: bool,
     show_world_inspector: bool,
+    show_animation_controls: bool,
+    /// Frame to jump to with the animation controls.
+    seek_frame: usize,
 }
 
 fn debug_ui_enabled(
@@ -34,6 +40,12 @@ fn show_world_inspector(
     debug_ui.enabled && debug_ui.show_world_inspector
 }
 
+fn show_animation_controls(
+    debug_ui: Res<DebugUi>,
+) -> bool {
+    debug_ui.enabled && debug_ui.show_animation_controls
+}
+
 fn debug_ui(
     mut debug_ui: ResMut<DebugUi>,
     mut debug_physics_ctx: ResMut<DebugRenderContext>,
@@ -49,12 +61,60 @@ fn debug_ui(
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("Debug", |ui| {
                     ui.checkbox(&mut debug_ui.show_world_inspector, "World Inspector");
+                    ui.checkbox(&mut debug_ui.show_animation_controls, "Animation Controls");
                     ui.checkbox(&mut debug_physics_ctx.enabled, "Debug Physics Render");
                 });
             });
         });
 }
 
+fn animation_controls_ui(
+    mut debug_ui: ResMut<DebugUi>,
+    mut animation_q: Query<(Entity, Option<&Name>, &mut AnimationState)>,
+    mut egui_ctx: EguiContexts,
+) {
+    egui::Window::new("Animation Controls")
+        .show(egui_ctx.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Seek frame");
+                ui.add(egui::DragValue::new(&mut debug_ui.seek_frame));
+            });
+
+            for (entity, name, mut state) in animation_q.iter_mut() {
+                ui.separator();
+                match name {
+                    Some(name) => ui.label(name.as_str()),
+                    None => ui.label(format!("{:?}", entity)),
+                };
+
+                ui.horizontal(|ui| {
+                    if state.is_paused() {
+                        if ui.button("Resume").clicked() {
+                            state.resume();
+                        }
+                    } else if ui.button("Pause").clicked() {
+                        state.pause();
+                    }
+                    if ui.button("Seek").clicked() {
+                        state.seek(debug_ui.seek_frame);
+                    }
+                    if ui.button("Play Once").clicked() {
+                        state.play_once();
+                    }
+                });
+
+                let mut speed = state.speed();
+                if ui.add(egui::Slider::new(&mut speed, 0.0..=4.0).text("Speed")).changed() {
+                    state.set_speed(speed);
+                }
+                let mut reversed = state.is_reversed();
+                if ui.checkbox(&mut reversed, "Reversed").changed() {
+                    state.set_reversed(reversed);
+                }
+            }
+        });
+}
+
 fn toggle_debug_ui(
     keys: ResMut<Input<KeyCode>>,
     mut debug_ui: ResMut<DebugUi>,
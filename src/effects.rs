@@ -0,0 +1,193 @@
+//! Short sprite effects that can be added to any entity.
+//!
+//! Each effect removes itself when it finishes and only writes the one thing it changes while it
+//! runs, so effects stack with each other and with systems that move or rotate the entity.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::tween::{Ease, Interpolate};
+
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (
+                squash_and_stretch,
+                blink,
+                tint_sprites::<Sprite>,
+                tint_sprites::<TextureAtlasSprite>,
+            ));
+    }
+}
+
+/// Scales an entity away from its normal size and springs it back. Only touches `Transform::scale`.
+#[derive(Component)]
+pub struct SquashStretch {
+    scale: Vec2,
+    timer: Timer,
+}
+
+impl SquashStretch {
+    pub fn new(scale: Vec2, duration: Duration) -> Self {
+        Self {
+            scale,
+            timer: Timer::new(duration, TimerMode::Once),
+        }
+    }
+}
+
+/// Toggles visibility every `interval` until the effect ends.
+#[derive(Component)]
+pub struct Blink {
+    interval: Duration,
+    timer: Timer,
+}
+
+impl Blink {
+    pub fn new(interval: Duration, duration: Duration) -> Self {
+        Self {
+            interval,
+            timer: Timer::new(duration, TimerMode::Once),
+        }
+    }
+}
+
+/// Fades a sprite's color from one tint to another, leaving it on the final tint.
+#[derive(Component)]
+pub struct TintFade {
+    from: Color,
+    to: Color,
+    timer: Timer,
+}
+
+impl TintFade {
+    pub fn new(from: Color, to: Color, duration: Duration) -> Self {
+        Self {
+            from,
+            to,
+            timer: Timer::new(duration, TimerMode::Once),
+        }
+    }
+}
+
+/// Shows a sprite in a solid color for a moment. Colors brighter than 1 push the sprite towards
+/// white, since sprite colors multiply the texture.
+#[derive(Component)]
+pub struct Flash {
+    color: Color,
+    timer: Timer,
+    /// Color to go back to, if there's no tint fade to go back to.
+    restore: Option<Color>,
+}
+
+impl Flash {
+    pub fn new(color: Color, duration: Duration) -> Self {
+        Self {
+            color,
+            timer: Timer::new(duration, TimerMode::Once),
+            restore: None,
+        }
+    }
+}
+
+/// Sprite components that color effects can tint.
+trait SpriteColor: Component {
+    fn color_mut(&mut self) -> &mut Color;
+}
+
+impl SpriteColor for Sprite {
+    fn color_mut(&mut self) -> &mut Color {
+        &mut self.color
+    }
+}
+
+impl SpriteColor for TextureAtlasSprite {
+    fn color_mut(&mut self) -> &mut Color {
+        &mut self.color
+    }
+}
+
+fn squash_and_stretch(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut effect_q: Query<(Entity, &mut SquashStretch, &mut Transform)>,
+) {
+    for (entity, mut effect, mut transform) in effect_q.iter_mut() {
+        effect.timer.tick(time.delta());
+        let ratio = Ease::ElasticOut.apply(effect.timer.percent());
+        transform.scale = effect.scale.lerp(Vec2::ONE, ratio).extend(1.0);
+
+        if effect.timer.finished() {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<SquashStretch>();
+        }
+    }
+}
+
+fn blink(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut effect_q: Query<(Entity, &mut Blink, &mut Visibility)>,
+) {
+    for (entity, mut effect, mut visibility) in effect_q.iter_mut() {
+        effect.timer.tick(time.delta());
+        if effect.timer.finished() {
+            visibility.set_if_neq(Visibility::Inherited);
+            commands.entity(entity).remove::<Blink>();
+            continue;
+        }
+
+        let flips = effect.timer.elapsed().as_nanos() / effect.interval.as_nanos().max(1);
+        let shown = if flips % 2 == 0 { Visibility::Inherited } else { Visibility::Hidden };
+        visibility.set_if_neq(shown);
+    }
+}
+
+/// Sprites with a tint fade or flash running.
+type TintedQuery<'w, 's, S> = Query<
+    'w,
+    's,
+    (Entity, &'static mut S, Option<&'static mut TintFade>, Option<&'static mut Flash>),
+    Or<(With<TintFade>, With<Flash>)>,
+>;
+
+/// Applies tint fades and flashes. Flashes draw over any running tint fade.
+fn tint_sprites<S: SpriteColor>(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut sprite_q: TintedQuery<S>,
+) {
+    for (entity, mut sprite, tint, flash) in sprite_q.iter_mut() {
+        let base = match tint {
+            Some(mut tint) => {
+                tint.timer.tick(time.delta());
+                if tint.timer.finished() {
+                    commands.entity(entity).remove::<TintFade>();
+                }
+                Color::interpolate(tint.from, tint.to, tint.timer.percent())
+            }
+            None => match &flash {
+                Some(flash) => flash.restore.unwrap_or(*sprite.color_mut()),
+                None => continue,
+            },
+        };
+
+        let color = match flash {
+            Some(mut flash) => {
+                flash.restore.get_or_insert(base);
+                flash.timer.tick(time.delta());
+                if flash.timer.finished() {
+                    commands.entity(entity).remove::<Flash>();
+                    base
+                } else {
+                    flash.color
+                }
+            }
+            None => base,
+        };
+        *sprite.color_mut() = color;
+    }
+}
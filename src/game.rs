@@ -1,17 +1,25 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
-use bevy::{
-    render::mesh::VertexAttributeValues,
-    sprite::Anchor,
-};
+use bevy::ecs::system::SystemParam;
+use bevy::sprite::MaterialMesh2dBundle;
 use bevy_egui::EguiContexts;
 use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     GAME_SIZE, AppState,
-    animation,
+    animation::{self, AnimationStateMachine, SwitchRule},
     assets::GameAssets,
-    camera::CameraShake,
-    window::WindowState,
+    camera::{self, CameraShake, GameView, PlayField, WorldCamera},
+    effects::{Blink, Flash, SquashStretch, TintFade},
+    parallax::ParallaxBackground,
+    particles::ParticleEmitter,
+    scrolling::{ScrollSpeed, ScrollingMaterial, ScrollingTexture},
+    skins::{BirdSkin, SelectedSkin},
+    themes::{ActiveTheme, Theme, ThemePart, Themes},
+    tween::{Animator, Delay, Ease, ReflectLens, RepeatCount, Sequence, TextColorLens, TransformScaleLens, Tween},
+    weather::WeatherForces,
 };
 
 // Bird constants
@@ -20,23 +28,42 @@ const BIRD_OFFSET_X: f32 = 30.0;
 const BIRD_GRAVITY: f32 = -650.0;
 const BIRD_MAX_FALL_SPEED: f32 = -400.0;
 const BIRD_JUMP_SPEED: f32 = 230.0;
+/// Below this speed the bird noses down and stops gliding.
+const BIRD_DIVE_SPEED: f32 = -110.0;
+/// How much faster the wings beat at full jump speed.
+const BIRD_CLIMB_FLAP_BOOST: f32 = 0.5;
+/// How strongly the bird is pulled back to its usual spot after wind pushes it sideways.
+const BIRD_DRIFT_RETURN: f32 = 1.5;
+/// Furthest wind can push the bird sideways.
+const BIRD_MAX_DRIFT: f32 = 20.0;
+/// How far the bird bobs up while waiting to start.
+const BIRD_BOB_HEIGHT: f32 = 4.0;
+/// Lowest the bird can go, resting on the ground.
+const BIRD_MIN_Y: f32 = GROUND_OFFSET * 2.0;
+/// Bright enough to turn the bird's sprite white.
+const BIRD_HIT_FLASH_COLOR: Color = Color::rgb(8.0, 8.0, 8.0);
+const BIRD_DEAD_TINT: Color = Color::rgb(0.6, 0.6, 0.7);
 
 // Pipe constants
 const PIPE_SPEED: f32 = 80.0;
-const PIPE_START_X: f32 = 210.0;
-const PIPE_END_X: f32 = -30.0;
 const PIPE_GAP: f32 = 70.0;
 const PIPE_WIDTH: f32 = 28.0;
 const PIPE_BODY_HEIGHT: f32 = 300.0;
 const PIPE_MOUTH_WIDTH: f32 = 32.0;
 const PIPE_MOUTH_HEIGHT: f32 = 16.0;
 const PIPE_SPACING: f32 = 120.0;
+/// Where the first pipe of a run starts.
 const PIPE_INIT_X: f32 = 200.0;
 const PIPE_Y_RAND_RANGE: f32 = 60.0;
 
 // Ground constants
 const GROUND_OFFSET: f32 = (GAME_SIZE.1 - 256.0) / 2.0;
 
+// Score text constants
+const SCORE_FONT_SIZE: f32 = 30.0;
+const SCORE_Y: f32 = 300.0;
+const SCORE_POP_COLOR: Color = Color::rgb(1.0, 0.85, 0.2);
+
 // Z values
 const BIRD_Z: f32 = 15.0;
 const PIPE_Z: f32 = 4.0;
@@ -48,10 +75,17 @@ impl Plugin for GamePlugin {
         app
             .add_state::<GameState>()
             .add_event::<TapEvent>()
+            .add_event::<BirdFlapped>()
+            .add_event::<BirdScored>()
+            .add_event::<BirdLanded>()
+            .add_event::<BirdCrashed>()
+            .add_event::<RunFinished>()
+            .init_resource::<GameMode>()
             .insert_resource(GameData::default())
 
             // OnEnter/OnExit systems.
             .add_systems(OnEnter(AppState::InGame), setup_game)
+            .add_systems(OnExit(AppState::InGame), teardown_game)
             .add_systems(OnEnter(GameState::Ready), (reset_bird, reset_pipes))
             .add_systems(OnEnter(GameState::Playing), enter_playing)
             .add_systems(OnExit(GameState::Playing), exit_playing)
@@ -63,8 +97,14 @@ impl Plugin for GamePlugin {
                 check_state_transition.run_if(not(in_state(GameState::Playing))).after(check_tap_input),
                 bird_movement.after(check_tap_input),
                 pipe_movement.run_if(in_state(GameState::Playing)).before(bird_movement),
+                spawn_pipes.after(pipe_movement),
+                update_scroll_speed,
+                fit_to_play_field.run_if(resource_changed::<PlayField>()),
                 check_bird_scored.run_if(in_state(GameState::Playing)).after(bird_movement),
                 check_bird_crashed.run_if(in_state(GameState::Playing)).after(bird_movement),
+                update_bird_animation.after(bird_movement).before(animation::animate_sprites),
+                play_bird_effects.after(bird_movement).after(check_bird_crashed),
+                check_exit_to_menu,
             ).run_if(in_state(AppState::InGame)));
 
         if cfg!(debug_assertions) {
@@ -74,24 +114,107 @@ impl Plugin for GamePlugin {
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, States)]
-enum GameState {
+pub enum GameState {
     #[default]
     Ready,
     Playing,
     Lost,
 }
 
+/// The rule set used for a run. Scores are tracked separately for each mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize, Resource)]
+pub enum GameMode {
+    #[default]
+    Classic,
+}
+
+impl GameMode {
+    pub const ALL: [Self; 1] = [Self::Classic];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Classic => "Classic",
+        }
+    }
+}
+
 #[derive(Default, Event)]
 struct TapEvent;
 
+/// Sent whenever the bird flaps, including the flap that starts a run.
+#[derive(Default, Event)]
+pub struct BirdFlapped;
+
+/// Sent when the falling bird comes to rest on the ground.
+#[derive(Default, Event)]
+struct BirdLanded;
+
+/// Sent when the bird passes through a pipe gap.
+#[derive(Default, Event)]
+pub struct BirdScored;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum CrashCause {
+    PipeTop,
+    PipeBottom,
+    Ground,
+}
+
+impl CrashCause {
+    pub const ALL: [Self; 3] = [Self::PipeTop, Self::PipeBottom, Self::Ground];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PipeTop => "Top pipe",
+            Self::PipeBottom => "Bottom pipe",
+            Self::Ground => "Ground",
+        }
+    }
+}
+
+/// Sent when the bird hits a pipe or the ground.
+#[derive(Debug, Event)]
+pub struct BirdCrashed {
+    pub cause: CrashCause,
+}
+
+/// Sent when the bird crashes, ending a run.
+#[derive(Debug, Event)]
+pub struct RunFinished {
+    pub mode: GameMode,
+    pub score: u16,
+    pub seed: u64,
+    pub duration: Duration,
+}
+
 #[derive(Default, Resource)]
-struct GameData {
-    score: u16,
+pub struct GameData {
+    pub score: u16,
     score_text: Option<Entity>,
     last_pipe_y: f32,
+    /// Seed for the current run's random number generator. Replaying a seed generates the same
+    /// pipes.
+    seed: u64,
+    rng: fastrand::Rng,
+    /// Time since startup when the current run started playing.
+    run_start: Duration,
 }
 
 impl GameData {
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Reseeds the random number generator for a new run.
+    fn start_run(&mut self) {
+        self.seed = fastrand::u64(..);
+        self.rng = fastrand::Rng::with_seed(self.seed);
+
+        // Initialize last_pipe_y in the center of the screen so we start generating new locations
+        // around it.
+        self.last_pipe_y = GAME_SIZE.1 / 2.0;
+    }
+
     fn gen_random_pipe_y(&mut self) -> f32 {
         const MIN: f32 = (GROUND_OFFSET * 2.0) + (PIPE_GAP * 0.75);
         const MAX: f32 = GAME_SIZE.1 - (PIPE_GAP * 0.75);
@@ -99,7 +222,7 @@ impl GameData {
         let range_min = (self.last_pipe_y - PIPE_Y_RAND_RANGE).max(MIN);
         let range_max = (self.last_pipe_y + PIPE_Y_RAND_RANGE).min(MAX);
 
-        let multiplier = fastrand::f32();
+        let multiplier = self.rng.f32();
         self.last_pipe_y = range_min + (range_max - range_min) * multiplier;
         // Round the position so that pipes are on integers and their sprites render properly.
         self.last_pipe_y = self.last_pipe_y.round();
@@ -110,38 +233,94 @@ impl GameData {
     }
 }
 
+/// Marks top-level entities spawned for a game session so they can be torn down when leaving
+/// `AppState::InGame`.
 #[derive(Default, Component)]
-struct Bird {
+struct GameEntity;
+
+#[derive(Default, Component)]
+pub struct Bird {
     speed: f32,
     angle: f32,
 }
 
+/// States of the bird's animation state machine, in the order of `SkinAnimations::clips`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BirdAnim {
+    Idle,
+    Flap,
+    Glide,
+    Fall,
+    Hit,
+    Dead,
+}
+
+impl BirdAnim {
+    pub const ALL: [Self; 6] = [Self::Idle, Self::Flap, Self::Glide, Self::Fall, Self::Hit, Self::Dead];
+
+    /// Name of the tag in the skin's sprite sheet.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::Flap => "flap",
+            Self::Glide => "glide",
+            Self::Fall => "fall",
+            Self::Hit => "hit",
+            Self::Dead => "dead",
+        }
+    }
+
+    pub fn clip(&self, animation: Handle<animation::Animation>) -> animation::AnimationClip {
+        let (speed, exit) = match self {
+            // Lazily flap while waiting for the run to start.
+            Self::Idle => (0.5, SwitchRule::Immediately),
+            // Finish the wing beat before gliding or falling.
+            Self::Flap => (1.0, SwitchRule::FinishCycle),
+            Self::Glide | Self::Fall => (1.0, SwitchRule::Immediately),
+            // Flail for a moment before going limp.
+            Self::Hit => (2.0, SwitchRule::FinishCycle),
+            Self::Dead => (1.0, SwitchRule::Immediately),
+        };
+        animation::AnimationClip { animation, speed, exit }
+    }
+}
+
 #[derive(Bundle)]
 struct BirdBundle {
     bird: Bird,
     name: Name,
     sprite_sheet: SpriteSheetBundle,
+    animation: Handle<animation::Animation>,
+    animation_state: animation::AnimationState,
+    animation_machine: AnimationStateMachine,
     rigid_body: RigidBody,
     collision_shape: Collider,
     sensor: Sensor,
+    collider_scale: ColliderScale,
     active_collision_types: ActiveCollisionTypes,
     active_events: ActiveEvents,
 }
 
 impl BirdBundle {
-    fn new(pos: Vec2, texture_atlas: Handle<TextureAtlas>) -> Self {
+    fn new(pos: Vec2, skin: BirdSkin, assets: &GameAssets) -> Self {
         let sprite_sheet = SpriteSheetBundle {
-            texture_atlas,
+            texture_atlas: skin.atlas(assets),
             transform: Transform::from_translation(pos.extend(BIRD_Z)),
             ..default()
         };
+        let animation_machine = AnimationStateMachine::new(skin.animations(assets).clips.clone(), BirdAnim::Idle as usize);
         Self {
             bird: Bird::default(),
             name: Name::new("Bird"),
             sprite_sheet,
+            animation: animation_machine.initial_animation(),
+            animation_state: default(),
+            animation_machine,
             rigid_body: RigidBody::KinematicPositionBased,
             collision_shape: Collider::ball(BIRD_RADIUS),
             sensor: Sensor,
+            // Keep the hitbox the same size while squashing and stretching.
+            collider_scale: ColliderScale::Absolute(Vec2::ONE),
             active_collision_types: ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_KINEMATIC,
             active_events: ActiveEvents::COLLISION_EVENTS,
         }
@@ -149,7 +328,7 @@ impl BirdBundle {
 }
 
 #[derive(Component)]
-struct Pipe;
+pub struct Pipe;
 
 #[derive(Bundle)]
 struct PipeBundle {
@@ -198,8 +377,26 @@ impl PipeScoreBundle {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PipeSide {
+    Top,
+    Bottom,
+}
+
+impl PipeSide {
+    fn from_offset(vertical_offset: f32) -> Self {
+        if vertical_offset > 0.0 {
+            Self::Top
+        } else {
+            Self::Bottom
+        }
+    }
+}
+
 #[derive(Component)]
-struct PipeBody;
+struct PipeBody {
+    side: PipeSide,
+}
 
 #[derive(Bundle)]
 struct PipeBodyBundle {
@@ -214,7 +411,9 @@ struct PipeBodyBundle {
 impl PipeBodyBundle {
     fn new(vertical_offset: f32, texture: Handle<Image>) -> Self {
         Self {
-            body: PipeBody,
+            body: PipeBody {
+                side: PipeSide::from_offset(vertical_offset),
+            },
             name: "PipeBody".into(),
             sprite_bundle: SpriteBundle {
                 transform: Transform::from_translation(Vec3::new(0.0, vertical_offset, PIPE_Z)),
@@ -245,7 +444,9 @@ struct PipeMouthBundle {
 impl PipeMouthBundle {
     fn new(vertical_offset: f32, sprite_index: usize, texture_atlas: Handle<TextureAtlas>) -> Self {
         Self {
-            body: PipeBody,
+            body: PipeBody {
+                side: PipeSide::from_offset(vertical_offset),
+            },
             name: "PipeMouth".into(),
             sprite_sheet: SpriteSheetBundle {
                 transform: Transform::from_translation(Vec3::new(0.0, vertical_offset, PIPE_Z + 1.0)),
@@ -263,51 +464,70 @@ impl PipeMouthBundle {
     }
 }
 
-fn get_pipe_init_x(pipe_index: u8) -> f32 {
-    PIPE_INIT_X + (pipe_index as f32 * PIPE_SPACING)
-}
-
 fn spawn_pipe(
     commands: &mut Commands,
-    assets: &GameAssets,
+    theme: &Theme,
     game_data: &mut GameData,
-    pipe_index: u8,
+    x: f32,
 ) {
-    commands.spawn(PipeBundle::new(Vec2::new(get_pipe_init_x(pipe_index), game_data.gen_random_pipe_y())))
+    commands.spawn(PipeBundle::new(Vec2::new(x, game_data.gen_random_pipe_y())))
+        .insert(GameEntity)
         .with_children(|parent| {
             // Score detection
             parent.spawn(PipeScoreBundle::new(20.0));
 
             // Top pipe
-            parent.spawn(PipeBodyBundle::new((PIPE_BODY_HEIGHT + PIPE_GAP) / 2.0, assets.pipe_center.clone()));
-            parent.spawn(PipeMouthBundle::new((PIPE_MOUTH_HEIGHT + PIPE_GAP) / 2.0, assets.terrain_indices.pipe_top, assets.terrain_atlas.clone()));
+            parent.spawn(PipeBodyBundle::new((PIPE_BODY_HEIGHT + PIPE_GAP) / 2.0, theme.pipe_center.clone()))
+                .insert(ThemePart::PipeBody);
+            parent.spawn(PipeMouthBundle::new((PIPE_MOUTH_HEIGHT + PIPE_GAP) / 2.0, theme.pipe_top, theme.terrain_atlas.clone()))
+                .insert(ThemePart::PipeTop);
 
             // Bottom pipe
-            parent.spawn(PipeBodyBundle::new(-(PIPE_BODY_HEIGHT + PIPE_GAP) / 2.0, assets.pipe_center.clone()));
-            parent.spawn(PipeMouthBundle::new(-(PIPE_MOUTH_HEIGHT + PIPE_GAP) / 2.0, assets.terrain_indices.pipe_bottom, assets.terrain_atlas.clone()));
+            parent.spawn(PipeBodyBundle::new(-(PIPE_BODY_HEIGHT + PIPE_GAP) / 2.0, theme.pipe_center.clone()))
+                .insert(ThemePart::PipeBody);
+            parent.spawn(PipeMouthBundle::new(-(PIPE_MOUTH_HEIGHT + PIPE_GAP) / 2.0, theme.pipe_bottom, theme.terrain_atlas.clone()))
+                .insert(ThemePart::PipeBottom);
         });
 }
 
+/// The assets and theme the game world is built from.
+#[derive(SystemParam)]
+struct SetupAssets<'w> {
+    assets: Res<'w, GameAssets>,
+    selected_skin: Res<'w, SelectedSkin>,
+    themes: Res<'w, Themes>,
+    active_theme: Res<'w, ActiveTheme>,
+    images: Res<'w, Assets<Image>>,
+    materials: ResMut<'w, Assets<ScrollingMaterial>>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+}
+
 fn setup_game(
     mut commands: Commands,
     mut next_state: ResMut<NextState<GameState>>,
-    assets: Res<GameAssets>,
+    mut next_app_state: ResMut<NextState<AppState>>,
     mut game_data: ResMut<GameData>,
-    window_state: Res<WindowState>,
-    images: Res<Assets<Image>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
+    game_view: Res<GameView>,
+    play_field: Res<PlayField>,
+    mut setup: SetupAssets,
 ) {
     debug!("Setting up game");
 
+    let SetupAssets { assets, selected_skin, themes, active_theme, images, materials, meshes } = &mut setup;
+    let Some(theme) = themes.get(active_theme.0) else {
+        error!("No themes could be built, so the game can't start");
+        next_app_state.set(AppState::MainMenu);
+        return;
+    };
+    let (Some(ground_image), Some(ground_top_image)) = (images.get(&theme.ground), images.get(&theme.ground_top)) else {
+        error!("The ground images for theme {} aren't loaded, so the game can't start", theme.name);
+        next_app_state.set(AppState::MainMenu);
+        return;
+    };
+
     // Spawn an orthographic camera rooted at the bottom left parented under a transform to support
-    // camera shake.
-    let mut camera_bundle = Camera2dBundle::default();
-    // Make the projection origin the bottom left so the camera at 0,0 will have values increasing
-    // up and to the right.
-    camera_bundle.projection.viewport_origin = Vec2::ZERO;
-    camera_bundle.projection.scale = 1.0 / window_state.scale as f32;
-    let camera_entity = commands.spawn(camera_bundle)
+    // camera shake. It renders the world at its native resolution for the screen to scale up.
+    let camera_entity = commands.spawn(game_view.world_camera())
         .insert(CameraShake {
             max_angle: 10.0,
             max_offset: 10.0,
@@ -317,85 +537,80 @@ fn setup_game(
         .id();
     commands.spawn(TransformBundle::default())
         .insert(Name::new("CameraParent"))
+        .insert(GameEntity)
         .add_child(camera_entity);
 
+    // Show the world in the window.
+    let screen_entity = game_view.spawn_screen(&mut commands);
+    commands.entity(screen_entity)
+        .insert(Name::new("Screen"))
+        .insert(GameEntity);
+
     // Spawn Bird
-    commands.spawn(BirdBundle::new(Vec2::new(BIRD_OFFSET_X, GAME_SIZE.1 / 2.0), assets.bird_atlas.clone()))
-        .insert(assets.bird_anim.clone())
-        .insert(animation::AnimationState::default())
-        .insert(animation::Play);
-
-    // Spawn background sprite.
-    let background_sprite = SpriteBundle {
-        // Positioned at the top of the camera view.
-        transform: Transform::from_translation(Vec3::new(GAME_SIZE.0 / 2.0, GAME_SIZE.1, 0.0)),
-        sprite: Sprite {
-            anchor: Anchor::TopCenter,
-            ..default()
-        },
-        texture: assets.background.clone(),
-        ..default()
-    };
-    commands.spawn(background_sprite)
-        .insert(Name::new("Background"));
+    commands.spawn(BirdBundle::new(Vec2::new(BIRD_OFFSET_X, GAME_SIZE.1 / 2.0), selected_skin.skin, assets))
+        .insert(animation::Play)
+        .insert(GameEntity);
+
+    // The background and parallax layers are tiled across the play field under this.
+    commands.spawn(SpatialBundle::default())
+        .insert(Name::new("Parallax"))
+        .insert(ParallaxBackground)
+        .insert(GameEntity);
 
     // Spawn tiling ground texture.
-    let ground_image_size = images.get(&assets.ground).unwrap().size();
-    let mut ground_mesh = Mesh::from(shape::Quad::default());
-    if let Some(VertexAttributeValues::Float32x2(uvs)) = ground_mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
-        for uv in uvs {
-            uv[0] *= GAME_SIZE.0 / ground_image_size.x as f32;
-            uv[1] *= (GROUND_OFFSET * 2.0) / ground_image_size.y as f32;
-        }
-    }
+    let ground_mesh = meshes.add(Mesh::from(shape::Quad::default()));
+    let ground_image_size = ground_image.size();
     let ground_transform = Transform {
-        translation: Vec3::new(GAME_SIZE.0 / 2.0, GROUND_OFFSET, 10.0),
-        scale: Vec3::new(GAME_SIZE.0, GROUND_OFFSET * 2.0, 1.0),
+        translation: Vec3::new(play_field.width / 2.0, GROUND_OFFSET, 10.0),
+        scale: Vec3::new(play_field.width, GROUND_OFFSET * 2.0, 1.0),
         ..default()
     };
-    let ground_bundle = ColorMesh2dBundle {
+    let ground_material = ScrollingMaterial {
+        repeat: Vec2::new(play_field.width / ground_image_size.x as f32, (GROUND_OFFSET * 2.0) / ground_image_size.y as f32),
+        ..theme.ground.clone().into()
+    };
+    let ground_bundle = MaterialMesh2dBundle {
         transform: ground_transform,
-        material: materials.add(assets.ground.clone().into()),
-        mesh: meshes.add(ground_mesh.into()).into(),
+        material: materials.add(ground_material),
+        mesh: ground_mesh.clone().into(),
         ..default()
     };
     commands.spawn(ground_bundle)
-        .insert(Name::new("Ground"));
+        .insert(Name::new("Ground"))
+        .insert(ThemePart::Ground)
+        .insert(ScrollingTexture { factor: 1.0 })
+        .insert(GameEntity);
 
     // Spawn tiling ground top texture.
-    let ground_image_size = images.get(&assets.ground_top).unwrap().size();
-    let mut ground_mesh = Mesh::from(shape::Quad::default());
-    if let Some(VertexAttributeValues::Float32x2(uvs)) = ground_mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
-        for uv in uvs {
-            uv[0] *= GAME_SIZE.0 / ground_image_size.x as f32;
-        }
-    }
+    let ground_image_size = ground_top_image.size();
     let ground_transform = Transform {
-        translation: Vec3::new(GAME_SIZE.0 / 2.0, (GROUND_OFFSET * 2.0) - (ground_image_size.y as f32 / 2.0), 11.0),
-        scale: Vec3::new(GAME_SIZE.0, ground_image_size.y as f32, 1.0),
+        translation: Vec3::new(play_field.width / 2.0, (GROUND_OFFSET * 2.0) - (ground_image_size.y as f32 / 2.0), 11.0),
+        scale: Vec3::new(play_field.width, ground_image_size.y as f32, 1.0),
         ..default()
     };
-    let ground_bundle = ColorMesh2dBundle {
+    let ground_material = ScrollingMaterial {
+        repeat: Vec2::new(play_field.width / ground_image_size.x as f32, 1.0),
+        ..theme.ground_top.clone().into()
+    };
+    let ground_bundle = MaterialMesh2dBundle {
         transform: ground_transform,
-        material: materials.add(assets.ground_top.clone().into()),
-        mesh: meshes.add(ground_mesh.into()).into(),
+        material: materials.add(ground_material),
+        mesh: ground_mesh.into(),
         ..default()
     };
     commands.spawn(ground_bundle)
-        .insert(Name::new("Grass"));
+        .insert(Name::new("Grass"))
+        .insert(ThemePart::GroundTop)
+        .insert(ScrollingTexture { factor: 1.0 })
+        .insert(GameEntity);
 
-    // Initialize last_pipe_y in the center of the screen so we start generating new locations
-    // around it.
-    game_data.last_pipe_y = GAME_SIZE.1 / 2.0;
-
-    // Spawn pipes offscreen.
-    spawn_pipe(&mut commands, &assets, &mut game_data, 0);
-    spawn_pipe(&mut commands, &assets, &mut game_data, 1);
+    // Pipes are spawned as they're needed once the run is reset.
+    game_data.start_run();
 
     // Create score text.
     let style = TextStyle {
         font: assets.font.clone(),
-        font_size: 30.0,
+        font_size: SCORE_FONT_SIZE,
         color: Color::WHITE,
     };
     let alignment = TextAlignment::Center;
@@ -403,10 +618,11 @@ fn setup_game(
         .spawn(Text2dBundle {
             text: Text::from_section("0", style.clone())
                 .with_alignment(alignment),
-            transform: Transform::from_translation(Vec3::new(GAME_SIZE.0 / 2.0, 300.0, 50.0)),
+            transform: Transform::from_translation(Vec3::new(play_field.width / 2.0, SCORE_Y, 50.0)),
             ..default()
         })
         .insert(Name::new("Score Text"))
+        .insert(GameEntity)
         .id();
     game_data.score_text = Some(score_text_id);
 
@@ -414,11 +630,31 @@ fn setup_game(
     next_state.set(GameState::Ready);
 }
 
+fn teardown_game(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut game_data: ResMut<GameData>,
+    mut scroll_speed: ResMut<ScrollSpeed>,
+    game_entity_q: Query<Entity, With<GameEntity>>,
+) {
+    debug!("Tearing down game");
+
+    for entity in game_entity_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    *game_data = GameData::default();
+    scroll_speed.0 = 0.0;
+
+    // Leave the game in the Ready state so the next session starts fresh.
+    next_state.set(GameState::Ready);
+}
+
 fn reset_bird(
     mut commands: Commands,
     app_state: Res<State<AppState>>,
     mut game_data: ResMut<GameData>,
-    mut bird_q: Query<(Entity, &mut Bird, &mut Transform)>,
+    mut bird_q: Query<(Entity, &mut Bird, &mut Transform, &mut TextureAtlasSprite, &mut AnimationStateMachine)>,
     mut score_text_q: Query<&mut Text>,
 ) {
     if *app_state.get() != AppState::InGame {
@@ -434,19 +670,41 @@ fn reset_bird(
         }
     }
 
-    for (entity, mut bird, mut transform) in bird_q.iter_mut() {
+    for (entity, mut bird, mut transform, mut sprite, mut animation_machine) in bird_q.iter_mut() {
         bird.speed = 0.0;
         bird.angle = 0.0;
         transform.translation = Vec3::new(BIRD_OFFSET_X, GAME_SIZE.1 / 2.0, BIRD_Z);
         transform.rotation = Quat::IDENTITY;
-        commands.entity(entity).insert(animation::Play);
+        transform.scale = Vec3::ONE;
+        sprite.color = Color::WHITE;
+        animation_machine.force(BirdAnim::Idle as usize);
+        commands.entity(entity)
+            .remove::<(SquashStretch, Flash, TintFade)>()
+            .insert(Blink::new(Duration::from_millis(100), Duration::from_millis(600)));
+
+        // Bob in place until the run starts.
+        let bob = Tween::new(
+                Ease::SineInOut,
+                Duration::from_millis(400),
+                ReflectLens {
+                    path: "translation.y".into(),
+                    start: transform.translation.y,
+                    end: transform.translation.y + BIRD_BOB_HEIGHT,
+                },
+            )
+            .with_repeat(RepeatCount::Infinite)
+            .with_yoyo(true);
+        let settle_then_bob = Sequence::new(Delay::new(Duration::from_millis(150)))
+            .then(bob);
+        commands.entity(entity).insert(Animator::<Transform>::new(settle_then_bob));
     }
 }
 
 fn reset_pipes(
+    mut commands: Commands,
     app_state: Res<State<AppState>>,
     mut game_data: ResMut<GameData>,
-    mut pipe_q: Query<&mut Transform, With<Pipe>>,
+    pipe_q: Query<Entity, With<Pipe>>,
 ) {
     if *app_state.get() != AppState::InGame {
         return;
@@ -454,37 +712,81 @@ fn reset_pipes(
 
     debug!("reset_pipes");
 
-    game_data.last_pipe_y = GAME_SIZE.1 / 2.0;
-    for (i, mut transform) in pipe_q.iter_mut().enumerate() {
-        transform.translation = Vec3::new(get_pipe_init_x(i as u8), game_data.gen_random_pipe_y(), 0.0);
+    // Start over with new pipes from the new seed.
+    game_data.start_run();
+    for entity in pipe_q.iter() {
+        commands.entity(entity).despawn_recursive();
     }
 }
 
+/// The world keeps scrolling while waiting to start, like the original game, and stops once the
+/// bird crashes. This follows the state rather than its transitions, since starting a session
+/// doesn't re-enter `Ready`.
+fn update_scroll_speed(
+    game_state: Res<State<GameState>>,
+    mut scroll_speed: ResMut<ScrollSpeed>,
+) {
+    let speed = match game_state.get() {
+        GameState::Ready | GameState::Playing => PIPE_SPEED,
+        GameState::Lost => 0.0,
+    };
+    scroll_speed.set_if_neq(ScrollSpeed(speed));
+}
+
 fn enter_playing(
-    mut bird_q: Query<&mut Bird>,
+    mut commands: Commands,
+    time: Res<Time>,
+    mut game_data: ResMut<GameData>,
+    mut flapped: EventWriter<BirdFlapped>,
+    mut bird_q: Query<(Entity, &mut Bird, &mut AnimationStateMachine)>,
 ) {
     debug!("Enter Playing");
 
-    for mut bird in bird_q.iter_mut() {
+    game_data.run_start = time.elapsed();
+    flapped.send_default();
+
+    for (entity, mut bird, mut animation_machine) in bird_q.iter_mut() {
         bird.speed = BIRD_JUMP_SPEED;
+        animation_machine.force(BirdAnim::Flap as usize);
+        // Stop bobbing so it doesn't fight bird movement.
+        commands.entity(entity).remove::<Animator<Transform>>();
     }
 }
 
 fn exit_playing(
-    mut commands: Commands,
-    mut bird_q: Query<(Entity, &mut Bird)>,
+    mut bird_q: Query<&mut Bird>,
 ) {
-    for (entity, mut bird) in bird_q.iter_mut() {
+    for mut bird in bird_q.iter_mut() {
         bird.speed = 0.0;
-        commands.entity(entity).remove::<animation::Play>();
     }
 }
 
 fn enter_lost(
+    time: Res<Time>,
+    game_mode: Res<GameMode>,
+    game_data: Res<GameData>,
+    mut run_finished: EventWriter<RunFinished>,
+    mut commands: Commands,
     mut camera_q: Query<&mut CameraShake>,
+    mut bird_q: Query<(&mut AnimationStateMachine, &Transform), With<Bird>>,
 ) {
     debug!("Enter Lost");
 
+    for (mut animation_machine, transform) in bird_q.iter_mut() {
+        animation_machine.force(BirdAnim::Hit as usize);
+
+        // Knock some debris off the bird.
+        commands.spawn(TransformBundle::from_transform(Transform::from_translation(transform.translation + Vec3::Z)))
+            .insert(ParticleEmitter::new("debris", Duration::ZERO));
+    }
+
+    run_finished.send(RunFinished {
+        mode: *game_mode,
+        score: game_data.score,
+        seed: game_data.seed,
+        duration: time.elapsed().saturating_sub(game_data.run_start),
+    });
+
     for mut shake in camera_q.iter_mut() {
         shake.add_trauma(0.4);
     }
@@ -513,31 +815,34 @@ fn check_state_transition(
     game_state: Res<State<GameState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
     mut tap_events: EventReader<TapEvent>,
-    bird_q: Query<&Transform, With<Bird>>,
 ) {
     // Making sure we drain the events.
     if tap_events.read().next().is_none() {
         return;
     }
 
-    match game_state.get() {
-        GameState::Ready => next_game_state.set(GameState::Playing),
-        GameState::Lost => {
-            // Make sure the bird has hit the ground before resetting.
-            if let Ok(bird_transform) = bird_q.get_single() {
-                if bird_transform.translation.y <= GROUND_OFFSET * 2.0 {
-                    next_game_state.set(GameState::Ready);
-                }
-            }
-        }
-        _ => {}
+    // Leaving the Lost state is handled by the results panel.
+    if *game_state.get() == GameState::Ready {
+        next_game_state.set(GameState::Playing);
     }
 }
 
+/// Run condition that's true once the bird has come to rest on the ground.
+pub fn bird_grounded(
+    bird_q: Query<&Transform, With<Bird>>,
+) -> bool {
+    bird_q.get_single()
+        .map(|transform| transform.translation.y <= GROUND_OFFSET * 2.0)
+        .unwrap_or(false)
+}
+
 fn bird_movement(
     game_state: Res<State<GameState>>,
     mut tap_events: EventReader<TapEvent>,
+    mut flapped: EventWriter<BirdFlapped>,
+    mut landed: EventWriter<BirdLanded>,
     time: Res<Time>,
+    weather: Res<WeatherForces>,
     mut bird_q: Query<(&mut Bird, &mut Transform)>,
 ) {
     if *game_state.get() == GameState::Ready {
@@ -546,16 +851,24 @@ fn bird_movement(
 
     let dt = time.delta_seconds();
 
-    let jumped = *game_state.get() == GameState::Playing && !tap_events.is_empty();
+    let playing = *game_state.get() == GameState::Playing;
+    // Wind stops pushing once the bird has crashed.
+    let wind = if playing { weather.wind } else { Vec2::ZERO };
+    let jumped = playing && !tap_events.is_empty();
     // Clear events since is_empty doesn't drain the reader.
     tap_events.clear();
+    if jumped {
+        flapped.send_default();
+    }
     for (mut bird, mut transform) in bird_q.iter_mut() {
+        let was_airborne = transform.translation.y > BIRD_MIN_Y;
+
         // Update velocity.
         if jumped {
             bird.speed = BIRD_JUMP_SPEED;
         } else {
-            // Fall with gravity.
-            bird.speed += BIRD_GRAVITY * dt;
+            // Fall with gravity, and get blown up or down by the wind.
+            bird.speed += (BIRD_GRAVITY * weather.gravity_scale + wind.y) * dt;
             bird.speed = bird.speed.max(BIRD_MAX_FALL_SPEED);
         }
 
@@ -567,13 +880,23 @@ fn bird_movement(
         }
 
         // Clamp position.
-        transform.translation.y = transform.translation.y.clamp(GROUND_OFFSET * 2.0, GAME_SIZE.1 - BIRD_RADIUS);
+        transform.translation.y = transform.translation.y.clamp(BIRD_MIN_Y, GAME_SIZE.1 - BIRD_RADIUS);
+        if was_airborne && transform.translation.y <= BIRD_MIN_Y {
+            landed.send_default();
+        }
+
+        // Drift with sideways wind, pulled back towards the bird's usual spot.
+        if playing {
+            let drift = transform.translation.x - BIRD_OFFSET_X;
+            transform.translation.x += (wind.x - drift * BIRD_DRIFT_RETURN) * dt;
+            transform.translation.x = transform.translation.x.clamp(BIRD_OFFSET_X - BIRD_MAX_DRIFT, BIRD_OFFSET_X + BIRD_MAX_DRIFT);
+        }
 
         // Set bird rotation based on speed.
         if bird.speed > 0.0 {
             // Rotate left.
             bird.angle += 600.0 * dt;
-        } else if bird.speed < -110.0 {
+        } else if bird.speed < BIRD_DIVE_SPEED {
             // Rotate right.
             bird.angle -= 480.0 * dt;
         }
@@ -582,24 +905,115 @@ fn bird_movement(
     }
 }
 
+fn update_bird_animation(
+    game_state: Res<State<GameState>>,
+    mut tap_events: EventReader<TapEvent>,
+    mut bird_q: Query<(&Bird, &mut AnimationStateMachine, &mut animation::AnimationState)>,
+) {
+    let tapped = tap_events.read().count() > 0;
+    for (bird, mut animation_machine, mut animation_state) in bird_q.iter_mut() {
+        // Beat the wings faster the harder the bird is climbing.
+        let climb = match game_state.get() {
+            GameState::Playing => (bird.speed / BIRD_JUMP_SPEED).clamp(0.0, 1.0),
+            _ => 0.0,
+        };
+        animation_state.set_speed(1.0 + climb * BIRD_CLIMB_FLAP_BOOST);
+
+        match game_state.get() {
+            GameState::Ready => animation_machine.request(BirdAnim::Idle as usize),
+            GameState::Playing if tapped => animation_machine.force(BirdAnim::Flap as usize),
+            GameState::Playing if bird.speed < BIRD_DIVE_SPEED => animation_machine.request(BirdAnim::Fall as usize),
+            GameState::Playing => animation_machine.request(BirdAnim::Glide as usize),
+            // Crashing starts the hit animation, which finishes before the bird goes limp.
+            GameState::Lost => animation_machine.request(BirdAnim::Dead as usize),
+        }
+    }
+}
+
 fn pipe_movement(
+    mut commands: Commands,
     time: Res<Time>,
-    mut game_data: ResMut<GameData>,
-    mut pipe_q: Query<&mut Transform, With<Pipe>>,
+    camera_q: Query<(&OrthographicProjection, &GlobalTransform), With<WorldCamera>>,
+    mut pipe_q: Query<(Entity, &mut Transform), With<Pipe>>,
 ) {
-    for mut transform in pipe_q.iter_mut() {
+    let Ok((projection, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+    let despawn_x = camera::visible_rect(projection, camera_transform).min.x - PIPE_MOUTH_WIDTH;
+
+    for (entity, mut transform) in pipe_q.iter_mut() {
         transform.translation.x -= PIPE_SPEED * time.delta_seconds();
 
-        // If scrolled past the left end of the screen, teleport to the right side.
-        if transform.translation.x < PIPE_END_X {
-            transform.translation.x = PIPE_START_X;
-            transform.translation.y = game_data.gen_random_pipe_y();
+        // Despawn pipes once they've scrolled past the left edge of the screen.
+        if transform.translation.x < despawn_x {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Keeps evenly spaced pipes lined up past the right edge of the screen, so wider play fields show
+/// more pipes.
+fn spawn_pipes(
+    mut commands: Commands,
+    themes: Res<Themes>,
+    active_theme: Res<ActiveTheme>,
+    mut game_data: ResMut<GameData>,
+    camera_q: Query<(&OrthographicProjection, &GlobalTransform), With<WorldCamera>>,
+    pipe_q: Query<&Transform, With<Pipe>>,
+) {
+    let Some(theme) = themes.get(active_theme.0) else {
+        return;
+    };
+    let Ok((projection, camera_transform)) = camera_q.get_single() else {
+        return;
+    };
+    let spawn_x = camera::visible_rect(projection, camera_transform).max.x + PIPE_MOUTH_WIDTH;
+
+    let mut last_x = pipe_q.iter()
+        .map(|transform| transform.translation.x)
+        .reduce(f32::max);
+    loop {
+        let x = last_x.map_or(PIPE_INIT_X, |last_x| last_x + PIPE_SPACING);
+        // Always have the first pipe, even if it starts offscreen.
+        if last_x.is_some() && x > spawn_x {
+            break;
+        }
+        spawn_pipe(&mut commands, theme, &mut game_data, x);
+        last_x = Some(x);
+    }
+}
+
+/// Stretches the ground and recenters the score when the play field changes width.
+fn fit_to_play_field(
+    play_field: Res<PlayField>,
+    game_data: Res<GameData>,
+    images: Res<Assets<Image>>,
+    mut materials: ResMut<Assets<ScrollingMaterial>>,
+    mut ground_q: Query<(&mut Transform, &Handle<ScrollingMaterial>), With<ScrollingTexture>>,
+    mut score_text_q: Query<&mut Transform, Without<ScrollingTexture>>,
+) {
+    for (mut transform, material_handle) in ground_q.iter_mut() {
+        transform.translation.x = play_field.width / 2.0;
+        transform.scale.x = play_field.width;
+        let Some(material) = materials.get_mut(material_handle) else {
+            continue;
+        };
+        if let Some(image) = images.get(&material.texture) {
+            material.repeat.x = play_field.width / image.size().x as f32;
+        }
+    }
+
+    if let Some(entity) = game_data.score_text {
+        if let Ok(mut transform) = score_text_q.get_mut(entity) {
+            transform.translation.x = play_field.width / 2.0;
         }
     }
 }
 
 fn check_bird_scored(
+    mut commands: Commands,
     mut collisions: EventReader<CollisionEvent>,
+    mut scored_events: EventWriter<BirdScored>,
     mut game_data: ResMut<GameData>,
     bird_q: Query<(), With<Bird>>,
     pipe_score_q: Query<(), With<PipeScoreZone>>,
@@ -613,10 +1027,12 @@ fn check_bird_scored(
         if let &CollisionEvent::Started(entity1, entity2, _flags) = event {
             let scored = bird_entered_score_zone(entity1, entity2) || bird_entered_score_zone(entity2, entity1);
             if scored {
+                scored_events.send_default();
                 game_data.score += 1;
                 if let Some(entity) = game_data.score_text {
                     if let Ok(mut text) = score_text_q.get_mut(entity) {
                         text.sections[0].value = game_data.score.to_string();
+                        commands.entity(entity).insert(score_pop());
                     }
                 }
             }
@@ -624,39 +1040,129 @@ fn check_bird_scored(
     }
 }
 
+/// Briefly grows the score text and flashes it gold. The text is scaled rather than resized, since
+/// every font size gets its own glyph atlas.
+fn score_pop() -> (Animator<Transform>, Animator<Text>) {
+    let grow = Tween::new(
+            Ease::QuadOut,
+            Duration::from_millis(90),
+            TransformScaleLens { start: Vec3::ONE, end: Vec3::splat(1.3) },
+        )
+        .with_repeat(RepeatCount::Finite(2))
+        .with_yoyo(true);
+    let flash = Tween::new(
+        Ease::QuadIn,
+        Duration::from_millis(300),
+        TextColorLens { section: 0, start: SCORE_POP_COLOR, end: Color::WHITE },
+    );
+    (Animator::new(grow), Animator::new(flash))
+}
+
+/// Squashes, stretches and flashes the bird in response to what it's doing, and kicks up feathers
+/// and dust.
+fn play_bird_effects(
+    mut commands: Commands,
+    mut flapped: EventReader<BirdFlapped>,
+    mut landed: EventReader<BirdLanded>,
+    mut crashed: EventReader<BirdCrashed>,
+    bird_q: Query<(Entity, &Transform), With<Bird>>,
+) {
+    let flapped = flapped.read().count() > 0;
+    let landed = landed.read().count() > 0;
+    let crashed = crashed.read().count() > 0;
+    let Ok((bird, transform)) = bird_q.get_single() else {
+        return;
+    };
+
+    if flapped {
+        commands.spawn(TransformBundle::from_transform(Transform::from_translation(transform.translation - Vec3::Z)))
+            .insert(ParticleEmitter::new("feathers", Duration::ZERO));
+    }
+    if landed {
+        // Skid along the ground for a moment.
+        let feet = Vec3::new(transform.translation.x, BIRD_MIN_Y - BIRD_RADIUS / 2.0, transform.translation.z + 1.0);
+        commands.spawn(TransformBundle::from_transform(Transform::from_translation(feet)))
+            .insert(ParticleEmitter::new("dust", Duration::from_millis(300)));
+    }
+
+    let mut bird = commands.entity(bird);
+    if flapped {
+        bird.insert(SquashStretch::new(Vec2::new(0.8, 1.25), Duration::from_millis(250)));
+    }
+    if landed {
+        bird.insert(SquashStretch::new(Vec2::new(1.4, 0.65), Duration::from_millis(350)));
+    }
+    if crashed {
+        bird.insert((
+            Flash::new(BIRD_HIT_FLASH_COLOR, Duration::from_millis(100)),
+            TintFade::new(Color::WHITE, BIRD_DEAD_TINT, Duration::from_millis(600)),
+        ));
+    }
+}
+
 fn check_bird_crashed(
     mut next_state: ResMut<NextState<GameState>>,
     mut collisions: EventReader<CollisionEvent>,
+    mut crashed_events: EventWriter<BirdCrashed>,
     bird_q: Query<&Transform, With<Bird>>,
-    pipe_body_q: Query<(), With<PipeBody>>,
+    pipe_body_q: Query<&PipeBody>,
 ) {
     // Check if bird hit the ground.
     if let Ok(transform) = bird_q.get_single() {
         if transform.translation.y <= (GROUND_OFFSET * 2.0) + BIRD_RADIUS {
             next_state.set(GameState::Lost);
+            crashed_events.send(BirdCrashed { cause: CrashCause::Ground });
             return;
         }
     }
 
     // Check if bird hit a pipe.
     let bird_hit_pipe = |entity1, entity2| {
-        bird_q.contains(entity1) && pipe_body_q.contains(entity2)
+        if bird_q.contains(entity1) {
+            pipe_body_q.get(entity2).ok()
+        } else {
+            None
+        }
     };
+    let mut hit_side = None;
     for event in collisions.read() {
         if let &CollisionEvent::Started(entity1, entity2, _flags) = event {
-            let hit_pipe = bird_hit_pipe(entity1, entity2) || bird_hit_pipe(entity2, entity1);
-            if hit_pipe {
-                next_state.set(GameState::Lost);
+            if let Some(pipe_body) = bird_hit_pipe(entity1, entity2).or_else(|| bird_hit_pipe(entity2, entity1)) {
+                // Only report the first pipe hit.
+                hit_side = hit_side.or(Some(pipe_body.side));
             }
         }
     }
+
+    if let Some(side) = hit_side {
+        next_state.set(GameState::Lost);
+        let cause = match side {
+            PipeSide::Top => CrashCause::PipeTop,
+            PipeSide::Bottom => CrashCause::PipeBottom,
+        };
+        crashed_events.send(BirdCrashed { cause });
+    }
+}
+
+fn check_exit_to_menu(
+    keys: Res<Input<KeyCode>>,
+    mut egui_ctx: EguiContexts,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if egui_ctx.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        next_app_state.set(AppState::MainMenu);
+    }
 }
 
 fn camera_control(
     keys: Res<Input<KeyCode>>,
     time: Res<Time>,
     mut egui_ctx: EguiContexts,
-    mut camera_q: Query<&mut Transform, With<Camera>>,
+    mut camera_q: Query<&mut Transform, With<CameraShake>>,
 ) {
     const CAMERA_MOVE_SPEED: f32 = 300.0;
 
@@ -679,3 +1185,101 @@ fn camera_control(
 
     camera_transform.translation += move_dir.extend(0.0) * CAMERA_MOVE_SPEED * time.delta_seconds();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::Animation;
+
+    fn test_assets() -> GameAssets {
+        GameAssets {
+            font: Handle::default(),
+            achievements: Handle::default(),
+            themes: Handle::default(),
+            day_night: Handle::default(),
+            particles: Handle::default(),
+            bird_yellow_sheet: Handle::default(),
+            bird_yellow: default(),
+            bird_blue_sheet: Handle::default(),
+            bird_blue: default(),
+            bird_red_sheet: Handle::default(),
+            bird_red: default(),
+        }
+    }
+
+    fn test_theme(app: &mut App) -> Theme {
+        let mut images = app.world.resource_mut::<Assets<Image>>();
+        Theme {
+            name: "Test".into(),
+            clear_color: Color::BLACK,
+            background: images.add(Image::default()),
+            terrain_atlas: Handle::default(),
+            pipe_top: 0,
+            pipe_bottom: 0,
+            pipe_center: images.add(Image::default()),
+            ground_top: images.add(Image::default()),
+            ground: images.add(Image::default()),
+            layers: Vec::new(),
+        }
+    }
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app
+            .add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Image>()
+            .init_asset::<TextureAtlas>()
+            .init_asset::<ScrollingMaterial>()
+            .init_asset::<Mesh>()
+            .init_asset::<Font>()
+            .init_asset::<Animation>()
+            .add_state::<AppState>()
+            .add_state::<GameState>()
+            .init_resource::<GameData>()
+            .init_resource::<SelectedSkin>()
+            .init_resource::<ActiveTheme>()
+            .init_resource::<ScrollSpeed>()
+            .init_resource::<GameView>()
+            .init_resource::<PlayField>()
+            .add_systems(OnEnter(AppState::InGame), setup_game)
+            .add_systems(OnExit(AppState::InGame), teardown_game)
+            .add_systems(OnEnter(GameState::Ready), (reset_bird, reset_pipes));
+
+        let theme = test_theme(&mut app);
+        app
+            .insert_resource(test_assets())
+            .insert_resource(Themes(vec![theme]));
+        app.update();
+        app
+    }
+
+    fn set_app_state(app: &mut App, state: AppState) {
+        app.world.resource_mut::<NextState<AppState>>().set(state);
+        // One update to run the transition and a second to apply any queued GameState change.
+        app.update();
+        app.update();
+    }
+
+    #[test]
+    fn leaving_game_despawns_all_game_entities() {
+        let mut app = test_app();
+        set_app_state(&mut app, AppState::MainMenu);
+        let baseline = app.world.entities().len();
+
+        for _ in 0..3 {
+            set_app_state(&mut app, AppState::InGame);
+            assert!(app.world.entities().len() > baseline);
+            assert!(app.world.resource::<GameData>().score_text.is_some());
+
+            app.world.resource_mut::<GameData>().score = 5;
+            app.world.resource_mut::<NextState<GameState>>().set(GameState::Lost);
+            app.update();
+
+            set_app_state(&mut app, AppState::MainMenu);
+            assert_eq!(app.world.entities().len(), baseline);
+            assert_eq!(app.world.resource::<GameData>().score, 0);
+            assert!(app.world.resource::<GameData>().score_text.is_none());
+            assert_eq!(*app.world.resource::<State<GameState>>().get(), GameState::Ready);
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiSettings};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppState,
+    game::{self, GameMode, GameState, RunFinished},
+    menu::MenuScreen,
+    storage, touch,
+};
+
+const LEADERBOARD_FILENAME: &str = "leaderboard.ron";
+const LEADERBOARD_SIZE: usize = 10;
+const DEFAULT_PLAYER_NAME: &str = "Player";
+const MAX_NAME_LEN: usize = 12;
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(storage::load::<Leaderboards>(LEADERBOARD_FILENAME))
+            .init_resource::<NameEntry>()
+            .add_systems(OnExit(GameState::Lost), clear_name_entry)
+            .add_systems(Update, (
+                record_finished_runs,
+                name_entry_ui
+                    .run_if(in_state(GameState::Lost))
+                    .run_if(game::bird_grounded)
+                    .run_if(name_entry_pending)
+                    .after(record_finished_runs),
+            ).run_if(in_state(AppState::InGame)))
+            .add_systems(Update, leaderboard_screen_ui
+                .run_if(in_state(AppState::MainMenu))
+                .run_if(resource_equals(MenuScreen::Leaderboard)));
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub score: u16,
+    /// When the run happened, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub seed: u64,
+    pub duration_secs: f32,
+}
+
+/// The top scores for each game mode, best first.
+#[derive(Default, Deserialize, Serialize, Resource)]
+pub struct Leaderboards {
+    /// The most recently entered player name, used to prefill name entry.
+    #[serde(default)]
+    pub player_name: String,
+    #[serde(default)]
+    pub modes: BTreeMap<GameMode, Vec<LeaderboardEntry>>,
+}
+
+impl Leaderboards {
+    pub fn entries(&self, mode: GameMode) -> &[LeaderboardEntry] {
+        self.modes.get(&mode).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn best_score(&self, mode: GameMode) -> Option<u16> {
+        self.entries(mode).first().map(|entry| entry.score)
+    }
+
+    pub fn qualifies(&self, mode: GameMode, score: u16) -> bool {
+        let entries = self.entries(mode);
+        score > 0 && (entries.len() < LEADERBOARD_SIZE || entries.last().map_or(true, |last| score > last.score))
+    }
+
+    /// Inserts an entry in score order, returning its rank if it made the leaderboard. Ties are
+    /// ranked below existing entries.
+    fn insert(&mut self, mode: GameMode, entry: LeaderboardEntry) -> Option<usize> {
+        if !self.qualifies(mode, entry.score) {
+            return None;
+        }
+
+        let entries = self.modes.entry(mode).or_default();
+        let rank = entries.partition_point(|existing| existing.score >= entry.score);
+        entries.insert(rank, entry);
+        entries.truncate(LEADERBOARD_SIZE);
+        Some(rank)
+    }
+
+    fn save(&self) {
+        storage::save(LEADERBOARD_FILENAME, self);
+    }
+}
+
+struct PendingName {
+    mode: GameMode,
+    rank: usize,
+    score: u16,
+    name: String,
+    focused: bool,
+}
+
+/// A leaderboard entry waiting for the player to enter their name.
+#[derive(Default, Resource)]
+pub(crate) struct NameEntry(Option<PendingName>);
+
+pub fn name_entry_pending(
+    name_entry: Res<NameEntry>,
+) -> bool {
+    name_entry.0.is_some()
+}
+
+fn clear_name_entry(
+    mut name_entry: ResMut<NameEntry>,
+) {
+    name_entry.0 = None;
+}
+
+fn record_finished_runs(
+    mut run_finished: EventReader<RunFinished>,
+    mut leaderboards: ResMut<Leaderboards>,
+    mut name_entry: ResMut<NameEntry>,
+) {
+    for run in run_finished.read() {
+        let name = if leaderboards.player_name.is_empty() {
+            DEFAULT_PLAYER_NAME.to_string()
+        } else {
+            leaderboards.player_name.clone()
+        };
+        let entry = LeaderboardEntry {
+            name: name.clone(),
+            score: run.score,
+            timestamp: storage::unix_timestamp(),
+            seed: run.seed,
+            duration_secs: run.duration.as_secs_f32(),
+        };
+
+        // Save right away with the last used name so the score isn't lost if the game is closed
+        // during name entry.
+        if let Some(rank) = leaderboards.insert(run.mode, entry) {
+            debug!("Run placed #{} on the {} leaderboard", rank + 1, run.mode.name());
+            leaderboards.save();
+            name_entry.0 = Some(PendingName {
+                mode: run.mode,
+                rank,
+                score: run.score,
+                name,
+                focused: false,
+            });
+        }
+    }
+}
+
+pub fn name_entry_ui(
+    touches: Res<Touches>,
+    egui_settings: Res<EguiSettings>,
+    mut leaderboards: ResMut<Leaderboards>,
+    mut name_entry: ResMut<NameEntry>,
+    mut egui_ctx: EguiContexts,
+) {
+    let Some(pending) = name_entry.0.as_mut() else {
+        return;
+    };
+
+    let mut submitted = false;
+    let window = egui::Window::new("New High Score")
+        .title_bar(false)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0]);
+    window.show(egui_ctx.ctx_mut(), |ui| {
+        ui.set_width(250.0);
+        ui.vertical_centered(|ui| {
+            ui.label(egui::RichText::new("New High Score!").size(30.0));
+            ui.label(egui::RichText::new(format!("#{} with {}", pending.rank + 1, pending.score)).size(24.0));
+            ui.label(egui::RichText::new("Enter your name:").size(20.0));
+
+            let text_edit = egui::TextEdit::singleline(&mut pending.name)
+                .char_limit(MAX_NAME_LEN)
+                .font(egui::TextStyle::Heading);
+            let response = ui.add(text_edit);
+            if !pending.focused {
+                response.request_focus();
+                pending.focused = true;
+            }
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                submitted = true;
+            }
+
+            let save = ui.button(egui::RichText::new("Save").size(30.0));
+            if save.clicked() || touch::just_touched(&touches, &egui_settings, save.rect) {
+                submitted = true;
+            }
+        });
+    });
+
+    if !submitted {
+        return;
+    }
+
+    let name = match pending.name.trim() {
+        "" => DEFAULT_PLAYER_NAME.to_string(),
+        name => name.to_string(),
+    };
+    if let Some(entry) = leaderboards.modes.get_mut(&pending.mode).and_then(|entries| entries.get_mut(pending.rank)) {
+        entry.name = name.clone();
+    }
+    leaderboards.player_name = name;
+    leaderboards.save();
+    name_entry.0 = None;
+}
+
+fn leaderboard_screen_ui(
+    touches: Res<Touches>,
+    egui_settings: Res<EguiSettings>,
+    leaderboards: Res<Leaderboards>,
+    mut menu_screen: ResMut<MenuScreen>,
+    mut egui_ctx: EguiContexts,
+) {
+    let window = egui::Window::new("Leaderboard")
+        .title_bar(false)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -50.0]);
+    window.show(egui_ctx.ctx_mut(), |ui| {
+        ui.set_width(320.0);
+        ui.vertical_centered(|ui| {
+            for mode in GameMode::ALL {
+                ui.label(egui::RichText::new(mode.name()).size(30.0));
+
+                let entries = leaderboards.entries(mode);
+                if entries.is_empty() {
+                    ui.label("No scores yet");
+                    continue;
+                }
+
+                egui::Grid::new(("leaderboard", mode))
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("#");
+                        ui.strong("Name");
+                        ui.strong("Score");
+                        ui.strong("Date");
+                        ui.strong("Time");
+                        ui.end_row();
+
+                        for (rank, entry) in entries.iter().enumerate() {
+                            ui.label((rank + 1).to_string());
+                            ui.label(entry.name.as_str());
+                            ui.label(entry.score.to_string());
+                            ui.label(storage::format_date(entry.timestamp));
+                            ui.label(format!("{:.1}s", entry.duration_secs));
+                            ui.end_row();
+                        }
+                    });
+            }
+
+            ui.add_space(10.0);
+            let back = ui.button(egui::RichText::new("Back").size(30.0));
+            if back.clicked() || touch::just_touched(&touches, &egui_settings, back.rect) {
+                *menu_screen = MenuScreen::Main;
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u16) -> LeaderboardEntry {
+        LeaderboardEntry {
+            name: name.into(),
+            score,
+            timestamp: 0,
+            seed: 0,
+            duration_secs: 0.0,
+        }
+    }
+
+    fn scores(leaderboards: &Leaderboards) -> Vec<u16> {
+        leaderboards.entries(GameMode::Classic).iter().map(|entry| entry.score).collect()
+    }
+
+    #[test]
+    fn insert_keeps_only_the_top_scores() {
+        let mut leaderboards = Leaderboards::default();
+        for score in 1..=LEADERBOARD_SIZE as u16 + 2 {
+            leaderboards.insert(GameMode::Classic, entry("Player", score));
+        }
+
+        let expected: Vec<u16> = (3..=LEADERBOARD_SIZE as u16 + 2).rev().collect();
+        assert_eq!(scores(&leaderboards), expected);
+    }
+
+    #[test]
+    fn insert_returns_rank() {
+        let mut leaderboards = Leaderboards::default();
+        assert_eq!(leaderboards.insert(GameMode::Classic, entry("A", 5)), Some(0));
+        assert_eq!(leaderboards.insert(GameMode::Classic, entry("B", 9)), Some(0));
+        assert_eq!(leaderboards.insert(GameMode::Classic, entry("C", 7)), Some(1));
+        assert_eq!(scores(&leaderboards), vec![9, 7, 5]);
+    }
+
+    #[test]
+    fn ties_rank_below_existing_entries() {
+        let mut leaderboards = Leaderboards::default();
+        leaderboards.insert(GameMode::Classic, entry("First", 5));
+        assert_eq!(leaderboards.insert(GameMode::Classic, entry("Second", 5)), Some(1));
+
+        let names: Vec<&str> = leaderboards.entries(GameMode::Classic).iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["First", "Second"]);
+    }
+
+    #[test]
+    fn ties_with_the_last_entry_dont_qualify_when_full() {
+        let mut leaderboards = Leaderboards::default();
+        for score in 1..=LEADERBOARD_SIZE as u16 {
+            leaderboards.insert(GameMode::Classic, entry("Player", score));
+        }
+
+        assert!(!leaderboards.qualifies(GameMode::Classic, 1));
+        assert_eq!(leaderboards.insert(GameMode::Classic, entry("Late", 1)), None);
+        assert!(leaderboards.qualifies(GameMode::Classic, 2));
+    }
+
+    #[test]
+    fn zero_never_qualifies() {
+        let leaderboards = Leaderboards::default();
+        assert!(!leaderboards.qualifies(GameMode::Classic, 0));
+        assert!(leaderboards.qualifies(GameMode::Classic, 1));
+    }
+}
@@ -3,15 +3,32 @@
 use bevy::prelude::*;
 use bevy::asset::AssetMetaCheck;
 use bevy::log::{self, LogPlugin};
-use bevy::window::{WindowMode, WindowResolution};
+use bevy::window::WindowResolution;
 use bevy_rapier2d::prelude::*;
 
+mod achievements;
 mod animation;
+mod aseprite;
 mod assets;
+mod atlas;
 mod camera;
+mod daynight;
 mod debug;
+mod effects;
 mod game;
+mod leaderboard;
 mod menu;
+mod parallax;
+mod particles;
+mod results;
+mod scrolling;
+mod skins;
+mod stats;
+mod storage;
+mod themes;
+mod touch;
+mod tween;
+mod weather;
 mod window;
 
 const GAME_SIZE: (f32, f32) = (180.0, 320.0);
@@ -31,8 +48,8 @@ fn main() {
     #[cfg(target_arch = "wasm32")]
     console_error_panic_hook::set_once();
 
-    // TODO: Try to initialize logging before this. Maybe we can also make this code run in a plugin.
-    let saved_window_state = window::load_window_state();
+    // Logging isn't set up yet, so any problem loading this is logged by the window plugin.
+    let (saved_window_state, window_state_error) = window::load_window_state();
 
     let mut app = App::new();
 
@@ -57,9 +74,9 @@ fn main() {
                 GAME_SIZE.0 * saved_window_state.scale as f32,
                 GAME_SIZE.1 * saved_window_state.scale as f32,
             ),
-            resizable: false,
+            resizable: true,
             position: saved_window_state.position,
-            mode: WindowMode::Windowed,
+            mode: saved_window_state.mode(),
             ..default()
         }),
         ..default()
@@ -81,7 +98,7 @@ fn main() {
         .add_plugins(default_plugins)
         .add_plugins(bevy_egui::EguiPlugin)
         .insert_resource(bevy_egui::EguiSettings {
-            // TODO: Take DPI scaling into account as well.
+            // Kept in sync with the window's size and DPI by the window plugin.
             scale_factor: (saved_window_state.scale as f64) / (DEFAULT_SCALE as f64),
             ..default()
         })
@@ -90,17 +107,33 @@ fn main() {
         // App setup
         .add_state::<AppState>()
         .add_plugins((
-            window::WindowPlugin::new(saved_window_state),
+            window::WindowPlugin::new(saved_window_state, window_state_error),
             assets::AssetsPlugin,
             animation::AnimationPlugin,
             debug::DebugPlugin,
             camera::CameraPlugin,
             menu::MenuPlugin,
             game::GamePlugin,
+            results::ResultsPlugin,
+            leaderboard::LeaderboardPlugin,
+            stats::StatsPlugin,
+            achievements::AchievementsPlugin,
+            skins::SkinsPlugin,
+            themes::ThemesPlugin,
+        ))
+        .add_plugins((
+            tween::TweenPlugin,
+            effects::EffectsPlugin,
+            particles::ParticlesPlugin,
+            scrolling::ScrollingPlugin,
+            parallax::ParallaxPlugin,
+            daynight::DayNightPlugin,
+            weather::WeatherPlugin,
         ));
 
     if ALLOW_EXIT {
-        app.add_systems(Update, bevy::window::close_on_esc);
+        // Escape returns to the main menu while in game, so only quit from the menu.
+        app.add_systems(Update, bevy::window::close_on_esc.run_if(in_state(AppState::MainMenu)));
     }
 
     app.run();
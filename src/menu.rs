@@ -1,53 +1,123 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 use bevy::app::AppExit;
-use bevy_egui::{egui, EguiContexts};
+use bevy_egui::{egui, EguiContexts, EguiSettings};
 
 use crate::{
     ALLOW_EXIT, AppState,
     assets::GameAssets,
+    touch,
+    tween::{Animator, Ease, Parallel, RepeatCount, TransformPositionLens, TransformScaleLens, Tween, TweenCompleted, TweenSystems},
 };
 
+const TITLE_POSITION: Vec3 = Vec3::new(0.0, 200.0, 0.0);
+const TITLE_FONT_SIZE: f32 = 80.0;
+/// How much bigger the title grows at the peak of each pulse.
+const TITLE_PULSE_SCALE: f32 = 1.075;
+/// Completion id of the title's slide in.
+const TITLE_SHOWN: u64 = 1;
+
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app
+            .init_resource::<MenuScreen>()
             .add_systems(OnEnter(AppState::MainMenu), setup_main_menu)
             .add_systems(OnExit(AppState::MainMenu), despawn_main_menu)
-            // TODO: Temp hack to work around bevy_egui not supporting touches. Remove once it does!
-            .add_systems(Update, main_menu_ui.run_if(in_state(AppState::MainMenu)));
+            .add_systems(Update, main_menu_ui
+                .run_if(in_state(AppState::MainMenu))
+                .run_if(resource_equals(MenuScreen::Main)))
+            // After the slide in's animator is removed, so it doesn't take the pulse with it.
+            .add_systems(Update, pulse_title
+                .after(TweenSystems)
+                .run_if(in_state(AppState::MainMenu)));
 
+        // TODO: Temp hack to work around bevy_egui not supporting touches. Remove once it does!
         if cfg!(target_arch = "wasm32") {
-            app.add_systems(Update, tap_to_start.run_if(in_state(AppState::MainMenu)));
+            app.add_systems(Update, tap_to_start
+                .run_if(in_state(AppState::MainMenu))
+                .run_if(resource_equals(MenuScreen::Main)));
         }
     }
 }
 
+/// Which page of the main menu is showing. Each page is drawn by the plugin that owns it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub enum MenuScreen {
+    #[default]
+    Main,
+    Leaderboard,
+    Stats,
+    Achievements,
+    Skins,
+    Themes,
+    Settings,
+}
+
 fn setup_main_menu(
     mut commands: Commands,
     assets: Res<GameAssets>,
+    mut menu_screen: ResMut<MenuScreen>,
 ) {
+    *menu_screen = MenuScreen::Main;
+
     // 2D camera to view Title Text
     commands.spawn(Camera2dBundle::default());
 
     let style = TextStyle {
         font: assets.font.clone(),
-        font_size: 80.0,
+        font_size: TITLE_FONT_SIZE,
         color: Color::WHITE,
     };
     let alignment = TextAlignment::Center;
+    let start = TITLE_POSITION + Vec3::Y * 300.0;
+    let slide_in = Parallel::new(Tween::new(
+            Ease::BackOut,
+            Duration::from_millis(600),
+            TransformPositionLens { start, end: TITLE_POSITION },
+        ))
+        .with(Tween::new(
+            Ease::QuadOut,
+            Duration::from_millis(600),
+            TransformScaleLens { start: Vec3::splat(0.6), end: Vec3::ONE },
+        ));
     commands
         .spawn(Text2dBundle {
             text: Text::from_section("Flappy\nBevy", style.clone())
                 .with_alignment(alignment),
-            transform: Transform::from_translation(Vec3::new(0.0, 200.0, 0.0)),
+            transform: Transform::from_translation(start),
             ..default()
-        });
+        })
+        .insert(Animator::new(slide_in).with_completed_id(TITLE_SHOWN));
 }
 
+/// Gently pulses the title once it has slid in.
+fn pulse_title(
+    mut commands: Commands,
+    mut completed: EventReader<TweenCompleted>,
+) {
+    for event in completed.read().filter(|event| event.id == TITLE_SHOWN) {
+        let pulse = Tween::new(
+                Ease::SineInOut,
+                Duration::from_millis(900),
+                TransformScaleLens { start: Vec3::ONE, end: Vec3::splat(TITLE_PULSE_SCALE) },
+            )
+            .with_repeat(RepeatCount::Infinite)
+            .with_yoyo(true);
+        if let Some(mut title) = commands.get_entity(event.entity) {
+            title.insert(Animator::<Transform>::new(pulse));
+        }
+    }
+}
+
+/// The main menu's camera and text.
+type MainMenuQuery<'w, 's> = Query<'w, 's, Entity, Or<(With<Camera>, With<Text>)>>;
+
 fn despawn_main_menu(
     mut commands: Commands,
-    query: Query<Entity, Or<(With<Camera>, With<Text>)>>,
+    query: MainMenuQuery,
 ) {
     for entity in query.iter() {
         commands.entity(entity).despawn();
@@ -57,18 +127,24 @@ fn despawn_main_menu(
 fn tap_to_start(
     buttons: Res<Input<MouseButton>>,
     touches: Res<Touches>,
+    egui_settings: Res<EguiSettings>,
+    mut egui_ctx: EguiContexts,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
-    let mouse_input = buttons.just_pressed(MouseButton::Left);
+    // Let clicks and taps on the other menu buttons through.
+    let mouse_input = !egui_ctx.ctx_mut().is_pointer_over_area() && buttons.just_pressed(MouseButton::Left);
     // TODO: This doesn't work cause bevy/winit don't support web touch events.
-    let touch_input = touches.iter_just_pressed().count() > 0;
+    let touch_input = touch::just_touched_outside(&touches, &egui_settings, egui_ctx.ctx_mut());
     if mouse_input || touch_input {
         next_state.set(AppState::InGame);
     }
 }
 
 fn main_menu_ui(
+    touches: Res<Touches>,
+    egui_settings: Res<EguiSettings>,
     mut next_state: ResMut<NextState<AppState>>,
+    mut menu_screen: ResMut<MenuScreen>,
     mut ctx: EguiContexts,
     mut exit: EventWriter<AppExit>,
 ) {
@@ -85,6 +161,36 @@ fn main_menu_ui(
                 next_state.set(AppState::InGame);
             }
 
+            let scores = ui.button(egui::RichText::new("Scores").size(30.0));
+            if scores.clicked() || touch::just_touched(&touches, &egui_settings, scores.rect) {
+                *menu_screen = MenuScreen::Leaderboard;
+            }
+
+            let stats = ui.button(egui::RichText::new("Stats").size(30.0));
+            if stats.clicked() || touch::just_touched(&touches, &egui_settings, stats.rect) {
+                *menu_screen = MenuScreen::Stats;
+            }
+
+            let achievements = ui.button(egui::RichText::new("Achievements").size(30.0));
+            if achievements.clicked() || touch::just_touched(&touches, &egui_settings, achievements.rect) {
+                *menu_screen = MenuScreen::Achievements;
+            }
+
+            let skins = ui.button(egui::RichText::new("Skins").size(30.0));
+            if skins.clicked() || touch::just_touched(&touches, &egui_settings, skins.rect) {
+                *menu_screen = MenuScreen::Skins;
+            }
+
+            let themes = ui.button(egui::RichText::new("Themes").size(30.0));
+            if themes.clicked() || touch::just_touched(&touches, &egui_settings, themes.rect) {
+                *menu_screen = MenuScreen::Themes;
+            }
+
+            let settings = ui.button(egui::RichText::new("Settings").size(30.0));
+            if settings.clicked() || touch::just_touched(&touches, &egui_settings, settings.rect) {
+                *menu_screen = MenuScreen::Settings;
+            }
+
             if ALLOW_EXIT {
                 let quit = egui::RichText::new("Quit").size(60.0);
                 if ui.button(quit).clicked() {
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use crate::{
+    GAME_SIZE,
+    camera::PlayField,
+    scrolling::ScrollSpeed,
+    themes::{self, ActiveTheme, ThemePart, Themes},
+    tween::{Animator, Ease, SpriteColorLens, Tween},
+};
+
+pub struct ParallaxPlugin;
+
+impl Plugin for ParallaxPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (
+                spawn_layers,
+                scroll_layers.after(spawn_layers),
+            ));
+    }
+}
+
+/// Holds the active theme's background and parallax layers, tiled across the play field. Layers
+/// are rebuilt whenever the theme or the play field's width changes.
+#[derive(Default, Component)]
+pub struct ParallaxBackground;
+
+#[derive(Component)]
+struct ParallaxLayer {
+    factor: f32,
+    width: f32,
+    offset: f32,
+}
+
+fn spawn_layers(
+    mut commands: Commands,
+    themes: Res<Themes>,
+    active_theme: Res<ActiveTheme>,
+    play_field: Res<PlayField>,
+    images: Res<Assets<Image>>,
+    background_q: Query<(Entity, Ref<ParallaxBackground>)>,
+) {
+    let Some(theme) = themes.get(active_theme.0) else {
+        return;
+    };
+
+    for (entity, background) in background_q.iter() {
+        if !background.is_added() && !active_theme.is_changed() && !play_field.is_changed() {
+            continue;
+        }
+
+        debug!("Spawning {} parallax layers for theme {}", theme.layers.len(), theme.name);
+
+        // The background is a layer that stays still behind the others.
+        let background_layer = images.get(&theme.background).map(|image| themes::ParallaxLayer {
+            texture: theme.background.clone(),
+            rect: Rect::from_corners(Vec2::ZERO, image.size().as_vec2()),
+            factor: 0.0,
+            z: 0.0,
+        });
+
+        commands.entity(entity).despawn_descendants();
+        commands.entity(entity).with_children(|parent| {
+            for layer in background_layer.iter().chain(&theme.layers) {
+                let size = layer.rect.size();
+                // Line layers up with the background image, which is centered and hangs from the top
+                // of the screen.
+                let left = (play_field.width - size.x) / 2.0;
+                let top = GAME_SIZE.1 - layer.rect.min.y;
+                // Start the first copy at or past the left edge of the screen, with enough copies after
+                // it to cover the screen at any offset.
+                let start = left.rem_euclid(size.x) - size.x;
+                let copies = ((play_field.width - start) / size.x).ceil() as usize + 1;
+
+                parent.spawn(SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, layer.z)))
+                    .insert(Name::new("ParallaxLayer"))
+                    .insert(ParallaxLayer {
+                        factor: layer.factor,
+                        width: size.x,
+                        offset: 0.0,
+                    })
+                    .with_children(|parent| {
+                        for i in 0..copies {
+                            let mut sprite = parent.spawn(SpriteBundle {
+                                transform: Transform::from_xyz(start + i as f32 * size.x, top, 0.0),
+                                sprite: Sprite {
+                                    rect: Some(layer.rect),
+                                    anchor: Anchor::TopLeft,
+                                    ..default()
+                                },
+                                texture: layer.texture.clone(),
+                                ..default()
+                            });
+                            sprite.insert(ThemePart::Parallax);
+
+                            // Fade in when the game starts.
+                            if background.is_added() {
+                                sprite.insert(Animator::new(Tween::new(
+                                    Ease::QuadOut,
+                                    Duration::from_millis(400),
+                                    SpriteColorLens { start: Color::BLACK, end: Color::WHITE },
+                                )));
+                            }
+                        }
+                    });
+            }
+        });
+    }
+}
+
+fn scroll_layers(
+    time: Res<Time>,
+    speed: Res<ScrollSpeed>,
+    mut layer_q: Query<(&mut ParallaxLayer, &mut Transform)>,
+) {
+    for (mut layer, mut transform) in layer_q.iter_mut() {
+        if layer.width <= 0.0 {
+            continue;
+        }
+        layer.offset = (layer.offset + speed.0 * layer.factor * time.delta_seconds()) % layer.width;
+        // Snap to whole pixels so pixel art doesn't shimmer.
+        transform.translation.x = -layer.offset.round();
+    }
+}
@@ -0,0 +1,365 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use bevy::prelude::*;
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    reflect::TypePath,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    assets::{self, GameAssets, RonAssetLoaderError},
+    atlas::SpriteAtlas,
+    tween::Interpolate,
+};
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (
+                emit_particles,
+                update_particles,
+            ).run_if(not(in_state(AppState::Loading))))
+            .add_systems(OnExit(AppState::InGame), despawn_particles);
+    }
+}
+
+#[derive(Deserialize)]
+struct ParticlePresetsFile {
+    presets: BTreeMap<String, EmitterPresetFile>,
+}
+
+#[derive(Deserialize)]
+struct EmitterPresetFile {
+    /// Particles spawned as soon as the emitter starts.
+    #[serde(default)]
+    burst: u32,
+    /// Particles spawned per second for as long as the emitter runs.
+    #[serde(default)]
+    rate: f32,
+    /// In seconds.
+    lifetime: (f32, f32),
+    speed: (f32, f32),
+    direction: (f32, f32),
+    #[serde(default)]
+    gravity: (f32, f32),
+    /// In degrees per second.
+    #[serde(default)]
+    spin: (f32, f32),
+    /// Turn particles to face the way they're moving instead of spinning.
+    #[serde(default)]
+    align: bool,
+    /// Half the width and height of the box around the emitter that particles spawn in.
+    #[serde(default)]
+    area: (f32, f32),
+    /// Size at the start and end of a particle's life.
+    size: (f32, f32),
+    colors: Vec<(u8, u8, u8, u8)>,
+    /// Path to a `.atlas.ron` manifest, relative to the presets file.
+    #[serde(default)]
+    atlas: Option<String>,
+    #[serde(default)]
+    frames: Vec<usize>,
+}
+
+struct EmitterPreset {
+    burst: u32,
+    rate: f32,
+    lifetime: (f32, f32),
+    speed: (f32, f32),
+    direction: (f32, f32),
+    gravity: Vec2,
+    spin: (f32, f32),
+    align: bool,
+    area: Vec2,
+    size: (f32, f32),
+    colors: Vec<Color>,
+    atlas: Option<Handle<SpriteAtlas>>,
+    frames: Vec<usize>,
+}
+
+impl EmitterPreset {
+    /// Color a particle has after living for `ratio` of its lifetime.
+    fn color_at(&self, ratio: f32) -> Color {
+        let Some(last) = self.colors.len().checked_sub(1) else {
+            return Color::WHITE;
+        };
+        let position = ratio.clamp(0.0, 1.0) * last as f32;
+        let index = (position as usize).min(last.saturating_sub(1));
+        let next = (index + 1).min(last);
+        Color::interpolate(self.colors[index], self.colors[next], position - index as f32)
+    }
+}
+
+/// Particle emitter presets by name, loaded from a `.particles.ron` file.
+#[derive(Asset, TypePath)]
+pub struct ParticlePresets {
+    presets: BTreeMap<String, Arc<EmitterPreset>>,
+}
+
+#[derive(Default)]
+pub struct ParticlePresetsLoader;
+
+impl AssetLoader for ParticlePresetsLoader {
+    type Asset = ParticlePresets;
+    type Settings = ();
+    type Error = RonAssetLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let file: ParticlePresetsFile = ron::de::from_bytes(&bytes)?;
+
+            let presets = file.presets.into_iter()
+                .map(|(name, preset)| {
+                    let colors = preset.colors.iter()
+                        .map(|&(r, g, b, a)| Color::rgba_u8(r, g, b, a))
+                        .collect();
+                    let preset = EmitterPreset {
+                        burst: preset.burst,
+                        rate: preset.rate,
+                        lifetime: preset.lifetime,
+                        speed: preset.speed,
+                        direction: preset.direction,
+                        gravity: Vec2::new(preset.gravity.0, preset.gravity.1),
+                        spin: preset.spin,
+                        align: preset.align,
+                        area: Vec2::new(preset.area.0, preset.area.1),
+                        size: preset.size,
+                        colors,
+                        atlas: preset.atlas.map(|path| load_context.load(assets::relative_path(load_context, &path))),
+                        frames: preset.frames,
+                    };
+                    (name, Arc::new(preset))
+                })
+                .collect();
+            Ok(ParticlePresets { presets })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["particles.ron"]
+    }
+}
+
+/// Spawns particles from a preset at its entity's position. Emitters burst when they start, then
+/// stream particles for `duration` before despawning themselves. Particles live on their own, so
+/// emitters should be top-level entities.
+#[derive(Component)]
+pub struct ParticleEmitter {
+    preset: String,
+    /// Degrees added to the preset's directions.
+    angle: f32,
+    timer: Timer,
+    burst_done: bool,
+    /// Fraction of a particle carried over between frames.
+    pending: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(preset: &str, duration: Duration) -> Self {
+        Self {
+            preset: preset.into(),
+            angle: 0.0,
+            timer: Timer::new(duration, TimerMode::Once),
+            burst_done: false,
+            pending: 0.0,
+        }
+    }
+
+    /// Turns every particle's direction by `degrees` counterclockwise.
+    pub fn with_angle(mut self, degrees: f32) -> Self {
+        self.angle = degrees;
+        self
+    }
+}
+
+#[derive(Component)]
+struct Particle {
+    preset: Arc<EmitterPreset>,
+    velocity: Vec2,
+    /// In radians per second.
+    spin: f32,
+    age: f32,
+    lifetime: f32,
+}
+
+fn random_in((min, max): (f32, f32)) -> f32 {
+    min + (max - min) * fastrand::f32()
+}
+
+fn spawn_particle(
+    commands: &mut Commands,
+    preset: &Arc<EmitterPreset>,
+    sprite_atlases: &Assets<SpriteAtlas>,
+    translation: Vec3,
+    angle: f32,
+) {
+    let direction = (random_in(preset.direction) + angle).to_radians();
+    let particle = Particle {
+        preset: preset.clone(),
+        velocity: Vec2::from_angle(direction) * random_in(preset.speed),
+        spin: random_in(preset.spin).to_radians(),
+        age: 0.0,
+        lifetime: random_in(preset.lifetime),
+    };
+    let offset = Vec2::new(
+        random_in((-preset.area.x, preset.area.x)),
+        random_in((-preset.area.y, preset.area.y)),
+    );
+    let transform = Transform {
+        translation: translation + offset.extend(0.0),
+        rotation: Quat::from_rotation_z(if preset.align { direction } else { 0.0 }),
+        scale: Vec3::splat(preset.size.0),
+    };
+    let color = preset.color_at(0.0);
+
+    // Sprites are a pixel across and scaled up to the particle's size.
+    let atlas = preset.atlas.as_ref()
+        .and_then(|handle| sprite_atlases.get(handle))
+        .map(|sprite_atlas| sprite_atlas.atlas.clone());
+    match atlas {
+        Some(texture_atlas) => {
+            let index = match preset.frames.len() {
+                0 => 0,
+                len => preset.frames[fastrand::usize(..len)],
+            };
+            commands.spawn(SpriteSheetBundle {
+                sprite: TextureAtlasSprite {
+                    index,
+                    color,
+                    custom_size: Some(Vec2::ONE),
+                    ..default()
+                },
+                texture_atlas,
+                transform,
+                ..default()
+            })
+            .insert(particle);
+        }
+        None => {
+            commands.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::ONE),
+                    ..default()
+                },
+                transform,
+                ..default()
+            })
+            .insert(particle);
+        }
+    }
+}
+
+fn emit_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    assets: Res<GameAssets>,
+    particle_presets: Res<Assets<ParticlePresets>>,
+    sprite_atlases: Res<Assets<SpriteAtlas>>,
+    mut emitter_q: Query<(Entity, &mut ParticleEmitter, &Transform)>,
+) {
+    let Some(particle_presets) = particle_presets.get(&assets.particles) else {
+        return;
+    };
+
+    for (entity, mut emitter, transform) in emitter_q.iter_mut() {
+        let Some(preset) = particle_presets.presets.get(&emitter.preset) else {
+            error!("Particle preset {} doesn't exist", emitter.preset);
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        let mut count = 0;
+        if !emitter.burst_done {
+            emitter.burst_done = true;
+            count += preset.burst;
+        }
+
+        // Only stream for the part of the frame the emitter was still running.
+        let running = time.delta().min(emitter.timer.remaining());
+        emitter.pending += preset.rate * running.as_secs_f32();
+        let streamed = emitter.pending.floor();
+        emitter.pending -= streamed;
+        count += streamed as u32;
+
+        for _ in 0..count {
+            spawn_particle(&mut commands, preset, &sprite_atlases, transform.translation, emitter.angle);
+        }
+
+        emitter.timer.tick(time.delta());
+        if emitter.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+type ParticleQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Particle,
+        &'static mut Transform,
+        Option<&'static mut Sprite>,
+        Option<&'static mut TextureAtlasSprite>,
+    ),
+>;
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particle_q: ParticleQuery,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut particle, mut transform, sprite, atlas_sprite) in particle_q.iter_mut() {
+        particle.age += dt;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let gravity = particle.preset.gravity;
+        particle.velocity += gravity * dt;
+        transform.translation += (particle.velocity * dt).extend(0.0);
+        if particle.preset.align {
+            transform.rotation = Quat::from_rotation_z(particle.velocity.y.atan2(particle.velocity.x));
+        } else {
+            transform.rotate_z(particle.spin * dt);
+        }
+
+        let ratio = particle.age / particle.lifetime;
+        let (start_size, end_size) = particle.preset.size;
+        transform.scale = Vec3::splat(f32::interpolate(start_size, end_size, ratio));
+        let color = particle.preset.color_at(ratio);
+        if let Some(mut sprite) = sprite {
+            sprite.color = color;
+        }
+        if let Some(mut sprite) = atlas_sprite {
+            sprite.color = color;
+        }
+    }
+}
+
+/// Every particle and emitter.
+type ParticleEntityQuery<'w, 's> = Query<'w, 's, Entity, Or<(With<Particle>, With<ParticleEmitter>)>>;
+
+fn despawn_particles(
+    mut commands: Commands,
+    particle_q: ParticleEntityQuery,
+) {
+    for entity in particle_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
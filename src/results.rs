@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    AppState,
+    game::{self, GameData, GameMode, GameState},
+    leaderboard::{self, Leaderboards},
+    touch::UiInput,
+};
+
+/// How long the results panel takes to slide into view.
+const SLIDE_IN_SECS: f32 = 0.4;
+/// How far below its resting place the results panel starts, in egui points.
+const SLIDE_IN_DISTANCE: f32 = 640.0;
+
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ResultsPanel>()
+            .add_systems(OnEnter(GameState::Lost), record_run_result)
+            // Wait for the player to enter their name if they placed on the leaderboard.
+            .add_systems(Update, results_ui
+                .run_if(in_state(AppState::InGame))
+                .run_if(in_state(GameState::Lost))
+                .run_if(game::bird_grounded)
+                .run_if(not(leaderboard::name_entry_pending))
+                .after(leaderboard::name_entry_ui));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+}
+
+impl Medal {
+    pub fn for_score(score: u16) -> Option<Self> {
+        match score {
+            40.. => Some(Self::Platinum),
+            30.. => Some(Self::Gold),
+            20.. => Some(Self::Silver),
+            10.. => Some(Self::Bronze),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bronze => "Bronze",
+            Self::Silver => "Silver",
+            Self::Gold => "Gold",
+            Self::Platinum => "Platinum",
+        }
+    }
+
+    fn color(&self) -> egui::Color32 {
+        match self {
+            Self::Bronze => egui::Color32::from_rgb(205, 127, 50),
+            Self::Silver => egui::Color32::from_rgb(192, 192, 192),
+            Self::Gold => egui::Color32::from_rgb(255, 215, 0),
+            Self::Platinum => egui::Color32::from_rgb(229, 228, 226),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResultsButton {
+    Retry,
+    Menu,
+}
+
+impl ResultsButton {
+    const ALL: [Self; 2] = [Self::Retry, Self::Menu];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Retry => "Retry",
+            Self::Menu => "Menu",
+        }
+    }
+}
+
+#[derive(Default, Resource)]
+struct ResultsPanel {
+    score: u16,
+    best: u16,
+    new_best: bool,
+    medal: Option<Medal>,
+    /// Progress of the slide in animation, from 0 to 1.
+    slide: f32,
+    selected: usize,
+}
+
+fn record_run_result(
+    game_mode: Res<GameMode>,
+    game_data: Res<GameData>,
+    leaderboards: Res<Leaderboards>,
+    mut panel: ResMut<ResultsPanel>,
+) {
+    // The leaderboard doesn't include this run yet, so compare against the previous best.
+    let score = game_data.score;
+    let previous_best = leaderboards.best_score(*game_mode).unwrap_or(0);
+
+    *panel = ResultsPanel {
+        score,
+        best: previous_best.max(score),
+        new_best: score > previous_best,
+        medal: Medal::for_score(score),
+        ..default()
+    };
+}
+
+fn results_ui(
+    time: Res<Time>,
+    input: UiInput,
+    mut panel: ResMut<ResultsPanel>,
+    mut egui_ctx: EguiContexts,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    let ctx = egui_ctx.ctx_mut();
+
+    panel.slide = (panel.slide + time.delta_seconds() / SLIDE_IN_SECS).min(1.0);
+    // Ease out so the panel decelerates into place.
+    let eased = 1.0 - (1.0 - panel.slide).powi(3);
+    let offset = (1.0 - eased) * SLIDE_IN_DISTANCE;
+
+    let mut activated = None;
+
+    // Keyboard navigation.
+    if !ctx.wants_keyboard_input() {
+        let count = ResultsButton::ALL.len();
+        if input.keys.any_just_pressed([KeyCode::Left, KeyCode::Up]) {
+            panel.selected = (panel.selected + count - 1) % count;
+        }
+        if input.keys.any_just_pressed([KeyCode::Right, KeyCode::Down, KeyCode::Tab]) {
+            panel.selected = (panel.selected + 1) % count;
+        }
+        if input.keys.any_just_pressed([KeyCode::Return, KeyCode::Space]) {
+            activated = Some(ResultsButton::ALL[panel.selected]);
+        }
+    }
+
+    let mut button_rects = Vec::with_capacity(ResultsButton::ALL.len());
+    let window = egui::Window::new("Results")
+        .title_bar(false)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, offset]);
+    window.show(ctx, |ui| {
+        ui.set_width(250.0);
+        ui.vertical_centered(|ui| {
+            ui.label(egui::RichText::new("Game Over").size(40.0));
+
+            match panel.medal {
+                Some(medal) => {
+                    let text = egui::RichText::new(format!("{} Medal", medal.name()))
+                        .size(30.0)
+                        .color(medal.color());
+                    ui.label(text);
+                }
+                None => {
+                    ui.label(egui::RichText::new("No Medal").size(30.0));
+                }
+            }
+
+            ui.label(egui::RichText::new(format!("Score: {}", panel.score)).size(30.0));
+            ui.label(egui::RichText::new(format!("Best: {}", panel.best)).size(30.0));
+            if panel.new_best {
+                ui.label(egui::RichText::new("New Best!").size(30.0).color(egui::Color32::YELLOW));
+            }
+
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                for (i, button) in ResultsButton::ALL.into_iter().enumerate() {
+                    let mut widget = egui::Button::new(egui::RichText::new(button.label()).size(40.0));
+                    if i == panel.selected {
+                        widget = widget.stroke(egui::Stroke::new(2.0, egui::Color32::WHITE));
+                    }
+                    let response = ui.add(widget);
+                    if response.hovered() {
+                        panel.selected = i;
+                    }
+                    if response.clicked() {
+                        activated = Some(button);
+                    }
+                    button_rects.push((button, response.rect));
+                }
+            });
+        });
+    });
+
+    for (button, rect) in button_rects {
+        if input.just_touched(rect) {
+            activated = Some(button);
+        }
+    }
+
+    // Ignore input until the panel has finished sliding in.
+    if panel.slide < 1.0 {
+        return;
+    }
+
+    match activated {
+        Some(ResultsButton::Retry) => next_game_state.set(GameState::Ready),
+        Some(ResultsButton::Menu) => next_app_state.set(AppState::MainMenu),
+        None => {}
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use bevy::{
+    reflect::TypePath,
+    render::render_resource::{AsBindGroup, ShaderRef},
+    sprite::{Material2d, Material2dPlugin},
+};
+
+pub struct ScrollingPlugin;
+
+impl Plugin for ScrollingPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugins(Material2dPlugin::<ScrollingMaterial>::default())
+            .init_resource::<ScrollSpeed>()
+            .add_systems(Update, scroll_textures);
+    }
+}
+
+/// A repeating texture that can be scrolled by changing its UV offset.
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct ScrollingMaterial {
+    #[uniform(0)]
+    pub color: Color,
+    /// Added to the mesh's UVs. One is a whole texture across.
+    #[uniform(0)]
+    pub offset: Vec2,
+    /// How many times the texture repeats across and down the mesh.
+    #[uniform(0)]
+    pub repeat: Vec2,
+    #[texture(1)]
+    #[sampler(2)]
+    pub texture: Handle<Image>,
+}
+
+impl From<Handle<Image>> for ScrollingMaterial {
+    fn from(texture: Handle<Image>) -> Self {
+        Self {
+            color: Color::WHITE,
+            offset: Vec2::ZERO,
+            repeat: Vec2::ONE,
+            texture,
+        }
+    }
+}
+
+impl Material2d for ScrollingMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/scrolling.wgsl".into()
+    }
+}
+
+/// How fast the world scrolls past, in pixels per second.
+#[derive(Default, PartialEq, Resource)]
+pub struct ScrollSpeed(pub f32);
+
+/// Scrolls an entity's `ScrollingMaterial` to the left at `factor` times the `ScrollSpeed`.
+#[derive(Component)]
+pub struct ScrollingTexture {
+    pub factor: f32,
+}
+
+fn scroll_textures(
+    time: Res<Time>,
+    speed: Res<ScrollSpeed>,
+    images: Res<Assets<Image>>,
+    mut materials: ResMut<Assets<ScrollingMaterial>>,
+    scrolling_q: Query<(&ScrollingTexture, &Handle<ScrollingMaterial>)>,
+) {
+    if speed.0 == 0.0 {
+        return;
+    }
+
+    for (scrolling, material_handle) in scrolling_q.iter() {
+        let Some(material) = materials.get_mut(material_handle) else {
+            continue;
+        };
+        let Some(width) = images.get(&material.texture).map(|image| image.size().x as f32) else {
+            continue;
+        };
+        if width > 0.0 {
+            let distance = speed.0 * scrolling.factor * time.delta_seconds();
+            material.offset.x = (material.offset.x + distance / width).fract();
+        }
+    }
+}
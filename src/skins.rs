@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy_egui::{egui, EguiContexts, EguiSettings};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppState,
+    achievements::{AchievementList, AchievementProgress},
+    animation::{self, Animation, AnimationClip},
+    aseprite::AsepriteSheet,
+    assets::GameAssets,
+    game::{BirdAnim, GameMode},
+    leaderboard::Leaderboards,
+    menu::MenuScreen,
+    storage, touch,
+};
+
+const SKIN_FILENAME: &str = "skin.ron";
+const PREVIEW_POSITION: Vec3 = Vec3::new(0.0, 40.0, 0.0);
+const PREVIEW_SCALE: f32 = 4.0;
+
+pub struct SkinsPlugin;
+
+impl Plugin for SkinsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(storage::load::<SelectedSkin>(SKIN_FILENAME))
+            .add_systems(Startup, reset_locked_skin)
+            .add_systems(OnEnter(AppState::MainMenu), spawn_skin_preview)
+            .add_systems(OnExit(AppState::MainMenu), despawn_skin_preview)
+            .add_systems(Update, (
+                skins_screen_ui.run_if(resource_equals(MenuScreen::Skins)),
+                update_skin_preview.run_if(resource_changed::<SelectedSkin>()),
+            ).run_if(in_state(AppState::MainMenu)));
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BirdSkin {
+    #[default]
+    Yellow,
+    Blue,
+    Red,
+}
+
+enum SkinUnlock {
+    Always,
+    /// Reach this score in any game mode.
+    BestScore(u16),
+    /// Unlock the achievement with this id. Its name is looked up in the achievement list.
+    Achievement { id: &'static str },
+}
+
+impl BirdSkin {
+    pub const ALL: [Self; 3] = [Self::Yellow, Self::Blue, Self::Red];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Yellow => "Yellow",
+            Self::Blue => "Blue",
+            Self::Red => "Red",
+        }
+    }
+
+    pub fn sheet(&self, assets: &GameAssets) -> Handle<AsepriteSheet> {
+        match self {
+            Self::Yellow => assets.bird_yellow_sheet.clone(),
+            Self::Blue => assets.bird_blue_sheet.clone(),
+            Self::Red => assets.bird_red_sheet.clone(),
+        }
+    }
+
+    pub fn animations<'a>(&self, assets: &'a GameAssets) -> &'a SkinAnimations {
+        match self {
+            Self::Yellow => &assets.bird_yellow,
+            Self::Blue => &assets.bird_blue,
+            Self::Red => &assets.bird_red,
+        }
+    }
+
+    pub fn atlas(&self, assets: &GameAssets) -> Handle<TextureAtlas> {
+        self.animations(assets).atlas.clone()
+    }
+
+    /// One of the skin's animations, for birds that aren't driven by a state machine.
+    pub fn animation(&self, assets: &GameAssets, anim: BirdAnim) -> Handle<Animation> {
+        self.animations(assets).clips.get(anim as usize)
+            .map(|clip| clip.animation.clone())
+            .unwrap_or_default()
+    }
+
+    fn unlock(&self) -> SkinUnlock {
+        match self {
+            Self::Yellow => SkinUnlock::Always,
+            Self::Blue => SkinUnlock::BestScore(20),
+            Self::Red => SkinUnlock::Achievement { id: "steady_flier" },
+        }
+    }
+
+    fn unlock_hint(&self, achievements: Option<&AchievementList>) -> String {
+        match self.unlock() {
+            SkinUnlock::Always => String::new(),
+            SkinUnlock::BestScore(score) => format!("Score {} to unlock", score),
+            SkinUnlock::Achievement { id } => {
+                let name = achievements
+                    .and_then(|list| list.get(id))
+                    .map_or(id, |achievement| achievement.name.as_str());
+                format!("Earn {} to unlock", name)
+            }
+        }
+    }
+
+    pub fn is_unlocked(&self, leaderboards: &Leaderboards, progress: &AchievementProgress) -> bool {
+        match self.unlock() {
+            SkinUnlock::Always => true,
+            SkinUnlock::BestScore(score) => GameMode::ALL.into_iter()
+                .filter_map(|mode| leaderboards.best_score(mode))
+                .any(|best| best >= score),
+            SkinUnlock::Achievement { id } => progress.is_unlocked(id),
+        }
+    }
+}
+
+/// A skin's atlas and its clip for each `BirdAnim`, in the same order.
+#[derive(Clone, Default)]
+pub struct SkinAnimations {
+    pub atlas: Handle<TextureAtlas>,
+    pub clips: Vec<AnimationClip>,
+}
+
+impl SkinAnimations {
+    /// Looks up each `BirdAnim` tag in the skin's sprite sheet.
+    pub fn from_sheet(sheet: &AsepriteSheet, atlas: Handle<TextureAtlas>) -> Self {
+        let clips = BirdAnim::ALL.into_iter()
+            .map(|anim| {
+                let animation = sheet.animations.get(anim.tag()).cloned();
+                if animation.is_none() {
+                    error!("Bird sprite sheet is missing the {} tag", anim.tag());
+                }
+                anim.clip(animation.unwrap_or_default())
+            })
+            .collect();
+        Self { atlas, clips }
+    }
+}
+
+/// The bird skin used for new runs.
+#[derive(Default, Deserialize, Serialize, Resource)]
+#[serde(default)]
+pub struct SelectedSkin {
+    pub skin: BirdSkin,
+}
+
+/// What's needed to tell whether a skin is unlocked and how to unlock it.
+#[derive(SystemParam)]
+struct SkinUnlocks<'w> {
+    assets: Res<'w, GameAssets>,
+    achievement_lists: Res<'w, Assets<AchievementList>>,
+    leaderboards: Res<'w, Leaderboards>,
+    progress: Res<'w, AchievementProgress>,
+}
+
+impl SkinUnlocks<'_> {
+    fn is_unlocked(&self, skin: BirdSkin) -> bool {
+        skin.is_unlocked(&self.leaderboards, &self.progress)
+    }
+
+    fn hint(&self, skin: BirdSkin) -> String {
+        skin.unlock_hint(self.achievement_lists.get(&self.assets.achievements))
+    }
+}
+
+/// Goes back to the default skin if the saved one isn't unlocked, like when the save was edited or
+/// the leaderboard was cleared.
+fn reset_locked_skin(
+    leaderboards: Res<Leaderboards>,
+    progress: Res<AchievementProgress>,
+    mut selected_skin: ResMut<SelectedSkin>,
+) {
+    if !selected_skin.skin.is_unlocked(&leaderboards, &progress) {
+        warn!("Saved skin {} isn't unlocked, using the default skin", selected_skin.skin.name());
+        selected_skin.skin = BirdSkin::default();
+    }
+}
+
+/// The animated bird shown in the main menu.
+#[derive(Component)]
+struct SkinPreview;
+
+fn spawn_skin_preview(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    selected_skin: Res<SelectedSkin>,
+) {
+    commands
+        .spawn(SpriteSheetBundle {
+            texture_atlas: selected_skin.skin.atlas(&assets),
+            transform: Transform::from_translation(PREVIEW_POSITION)
+                .with_scale(Vec3::splat(PREVIEW_SCALE)),
+            ..default()
+        })
+        .insert(selected_skin.skin.animation(&assets, BirdAnim::Idle))
+        .insert(animation::AnimationState::default())
+        .insert(animation::AnimationQueue::default())
+        .insert(animation::Play)
+        .insert(SkinPreview)
+        .insert(Name::new("Skin Preview"));
+}
+
+fn despawn_skin_preview(
+    mut commands: Commands,
+    preview_q: Query<Entity, With<SkinPreview>>,
+) {
+    for entity in preview_q.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+type PreviewQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Handle<TextureAtlas>,
+        &'static mut Handle<Animation>,
+        &'static mut animation::AnimationState,
+        &'static mut animation::AnimationQueue,
+    ),
+    With<SkinPreview>,
+>;
+
+fn update_skin_preview(
+    assets: Res<GameAssets>,
+    selected_skin: Res<SelectedSkin>,
+    mut preview_q: PreviewQuery,
+) {
+    // Show off the new skin with a flap before going back to idling.
+    for (mut atlas, mut anim, mut state, mut queue) in preview_q.iter_mut() {
+        *atlas = selected_skin.skin.atlas(&assets);
+        *anim = selected_skin.skin.animation(&assets, BirdAnim::Flap);
+        state.play_once();
+        queue.clear();
+        queue.push_back(selected_skin.skin.animation(&assets, BirdAnim::Idle));
+    }
+}
+
+fn skins_screen_ui(
+    touches: Res<Touches>,
+    egui_settings: Res<EguiSettings>,
+    unlocks: SkinUnlocks,
+    mut selected_skin: ResMut<SelectedSkin>,
+    mut menu_screen: ResMut<MenuScreen>,
+    mut egui_ctx: EguiContexts,
+) {
+    let window = egui::Window::new("Skins")
+        .title_bar(false)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -50.0]);
+    window.show(egui_ctx.ctx_mut(), |ui| {
+        ui.set_width(250.0);
+        ui.vertical_centered_justified(|ui| {
+            ui.label(egui::RichText::new("Skins").size(30.0));
+
+            for skin in BirdSkin::ALL {
+                if !unlocks.is_unlocked(skin) {
+                    ui.add_enabled(false, egui::Button::new(egui::RichText::new(unlocks.hint(skin)).size(20.0)));
+                    continue;
+                }
+
+                let is_selected = selected_skin.skin == skin;
+                let response = ui.selectable_label(is_selected, egui::RichText::new(skin.name()).size(30.0));
+                let picked = response.clicked() || touch::just_touched(&touches, &egui_settings, response.rect);
+                if picked && !is_selected {
+                    selected_skin.skin = skin;
+                    storage::save(SKIN_FILENAME, &*selected_skin);
+                }
+            }
+
+            ui.add_space(10.0);
+            let back = ui.button(egui::RichText::new("Back").size(30.0));
+            if back.clicked() || touch::just_touched(&touches, &egui_settings, back.rect) {
+                *menu_screen = MenuScreen::Main;
+            }
+        });
+    });
+}
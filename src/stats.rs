@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiSettings};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppState,
+    game::{BirdCrashed, BirdFlapped, BirdScored, CrashCause, RunFinished},
+    menu::MenuScreen,
+    storage, touch,
+};
+
+const STATS_FILENAME: &str = "stats.ron";
+/// Width of each score range in the stats screen histogram.
+const HISTOGRAM_BUCKET_SIZE: u16 = 10;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(storage::load::<LifetimeStats>(STATS_FILENAME))
+            // Save flaps, pipes and deaths from runs that were abandoned partway through.
+            .add_systems(OnExit(AppState::InGame), save_stats)
+            .add_systems(Update, track_stats.run_if(in_state(AppState::InGame)))
+            .add_systems(Update, stats_screen_ui
+                .run_if(in_state(AppState::MainMenu))
+                .run_if(resource_equals(MenuScreen::Stats)));
+    }
+}
+
+/// Statistics accumulated across every session.
+#[derive(Default, Deserialize, Serialize, Resource)]
+#[serde(default)]
+pub struct LifetimeStats {
+    pub total_runs: u32,
+    pub total_flaps: u64,
+    pub pipes_passed: u64,
+    pub deaths: BTreeMap<CrashCause, u32>,
+    pub longest_run_secs: f32,
+    /// Sum of every run's score, used to compute the average.
+    pub total_score: u64,
+    /// Number of runs that ended with each score.
+    pub score_histogram: BTreeMap<u16, u32>,
+}
+
+impl LifetimeStats {
+    pub fn average_score(&self) -> f32 {
+        if self.total_runs == 0 {
+            return 0.0;
+        }
+        self.total_score as f32 / self.total_runs as f32
+    }
+
+    pub fn deaths_by(&self, cause: CrashCause) -> u32 {
+        self.deaths.get(&cause).copied().unwrap_or(0)
+    }
+
+    /// Groups the score histogram into ranges of `HISTOGRAM_BUCKET_SIZE`, lowest first.
+    fn bucketed_histogram(&self) -> BTreeMap<u16, u32> {
+        let mut buckets = BTreeMap::new();
+        for (score, count) in &self.score_histogram {
+            *buckets.entry(score / HISTOGRAM_BUCKET_SIZE).or_default() += count;
+        }
+        buckets
+    }
+}
+
+fn save_stats(
+    stats: Res<LifetimeStats>,
+) {
+    storage::save(STATS_FILENAME, &*stats);
+}
+
+fn track_stats(
+    mut flapped: EventReader<BirdFlapped>,
+    mut scored: EventReader<BirdScored>,
+    mut crashed: EventReader<BirdCrashed>,
+    mut run_finished: EventReader<RunFinished>,
+    mut stats: ResMut<LifetimeStats>,
+) {
+    stats.total_flaps += flapped.read().count() as u64;
+    stats.pipes_passed += scored.read().count() as u64;
+
+    for crash in crashed.read() {
+        *stats.deaths.entry(crash.cause).or_default() += 1;
+    }
+
+    let mut finished_run = false;
+    for run in run_finished.read() {
+        stats.total_runs += 1;
+        stats.total_score += run.score as u64;
+        stats.longest_run_secs = stats.longest_run_secs.max(run.duration.as_secs_f32());
+        *stats.score_histogram.entry(run.score).or_default() += 1;
+        finished_run = true;
+    }
+
+    if finished_run {
+        storage::save(STATS_FILENAME, &*stats);
+    }
+}
+
+fn stats_screen_ui(
+    touches: Res<Touches>,
+    egui_settings: Res<EguiSettings>,
+    stats: Res<LifetimeStats>,
+    mut menu_screen: ResMut<MenuScreen>,
+    mut egui_ctx: EguiContexts,
+) {
+    let window = egui::Window::new("Stats")
+        .title_bar(false)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -50.0]);
+    window.show(egui_ctx.ctx_mut(), |ui| {
+        ui.set_width(320.0);
+        ui.vertical_centered(|ui| {
+            ui.label(egui::RichText::new("Stats").size(30.0));
+
+            egui::Grid::new("lifetime_stats")
+                .striped(true)
+                .show(ui, |ui| {
+                    let mut row = |name: &str, value: String| {
+                        ui.label(name);
+                        ui.label(value);
+                        ui.end_row();
+                    };
+                    row("Runs", stats.total_runs.to_string());
+                    row("Flaps", stats.total_flaps.to_string());
+                    row("Pipes passed", stats.pipes_passed.to_string());
+                    row("Average score", format!("{:.1}", stats.average_score()));
+                    row("Longest run", format!("{:.1}s", stats.longest_run_secs));
+                    for cause in CrashCause::ALL {
+                        row(&format!("Deaths: {}", cause.name()), stats.deaths_by(cause).to_string());
+                    }
+                });
+
+            ui.add_space(10.0);
+            ui.label(egui::RichText::new("Scores").size(24.0));
+            let buckets = stats.bucketed_histogram();
+            let max_count = buckets.values().copied().max().unwrap_or(0);
+            if max_count == 0 {
+                ui.label("No runs yet");
+            }
+            for (bucket, count) in buckets {
+                let min = bucket * HISTOGRAM_BUCKET_SIZE;
+                let max = min.saturating_add(HISTOGRAM_BUCKET_SIZE - 1);
+                let bar = egui::ProgressBar::new(count as f32 / max_count as f32)
+                    .text(format!("{}-{}: {}", min, max, count));
+                ui.add(bar);
+            }
+
+            ui.add_space(10.0);
+            let back = ui.button(egui::RichText::new("Back").size(30.0));
+            if back.clicked() || touch::just_touched(&touches, &egui_settings, back.rect) {
+                *menu_screen = MenuScreen::Main;
+            }
+        });
+    });
+}
@@ -0,0 +1,192 @@
+//! Saving and loading small RON data files. On desktop these are written to the platform's config
+//! directory, and on the web they're kept in the browser's local storage.
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Loads a value previously saved under `name`, falling back to the default if it's missing or
+/// can't be read.
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    match read(name) {
+        Ok(value) => value.unwrap_or_default(),
+        Err(e) => {
+            error!("Could not load {}: {}", name, e);
+            default()
+        }
+    }
+}
+
+/// Reads a value previously saved under `name`, or `None` if nothing was saved. Unlike `load`, this
+/// doesn't log anything, so it can be used before logging is set up.
+pub fn read<T: DeserializeOwned>(name: &str) -> Result<Option<T>, String> {
+    let Some(contents) = read_string(name)? else {
+        return Ok(None);
+    };
+    ron::from_str(&contents)
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+/// Saves a value under `name`. Failures are logged rather than propagated since losing a save
+/// shouldn't interrupt the game.
+pub fn save<T: Serialize>(name: &str, value: &T) {
+    let pretty_config = ron::ser::PrettyConfig::default();
+    let contents = match ron::ser::to_string_pretty(value, pretty_config) {
+        Ok(contents) => contents,
+        Err(e) => {
+            error!("Could not serialize {}: {}", name, e);
+            return;
+        }
+    };
+
+    if let Err(e) = write_string(name, &contents) {
+        error!("Could not save {}: {}", name, e);
+    }
+}
+
+/// Name of the directory save files go in, inside the platform's config directory.
+#[cfg(not(target_arch = "wasm32"))]
+const APP_DIR_NAME: &str = "flappy-bevy";
+
+/// The platform's config directory: `%APPDATA%` on Windows, `~/Library/Application Support` on
+/// macOS and `$XDG_CONFIG_HOME` or `~/.config` everywhere else.
+#[cfg(not(target_arch = "wasm32"))]
+fn platform_config_dir() -> Option<std::path::PathBuf> {
+    use std::{env, path::PathBuf};
+
+    let absolute = |var: &str| env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute());
+
+    if cfg!(target_os = "windows") {
+        absolute("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        absolute("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        absolute("XDG_CONFIG_HOME").or_else(|| absolute("HOME").map(|home| home.join(".config")))
+    }
+}
+
+/// Directory save files are kept in. Falls back to the working directory if there's no config
+/// directory.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_dir() -> &'static std::path::Path {
+    use std::{path::PathBuf, sync::OnceLock};
+
+    static SAVE_DIR: OnceLock<PathBuf> = OnceLock::new();
+    SAVE_DIR.get_or_init(|| {
+        platform_config_dir()
+            .map(|dir| dir.join(APP_DIR_NAME))
+            .unwrap_or_else(|| PathBuf::from("."))
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_string(name: &str) -> Result<Option<String>, String> {
+    use std::path::Path;
+
+    // Older versions saved to the working directory, so fall back to that until the file is saved
+    // again.
+    let path = save_dir().join(name);
+    let path = if path.is_file() { path.as_path() } else { Path::new(name) };
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    std::fs::read_to_string(path)
+        .map(Some)
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_string(name: &str, contents: &str) -> Result<(), String> {
+    let dir = save_dir();
+    std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+
+    // Write to a temporary file first and rename it over the original so a crash mid-write can't
+    // leave a truncated file behind.
+    let path = dir.join(name);
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, contents).map_err(|e| format!("{}: {}", tmp_path.display(), e))?;
+    std::fs::rename(&tmp_path, &path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage_key(name: &str) -> String {
+    format!("flappy-bevy/{}", name)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_string(name: &str) -> Result<Option<String>, String> {
+    let Some(storage) = local_storage() else {
+        return Ok(None);
+    };
+    storage.get_item(&storage_key(name))
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_string(name: &str, contents: &str) -> Result<(), String> {
+    let storage = local_storage().ok_or_else(|| "local storage is unavailable".to_string())?;
+    storage.set_item(&storage_key(name), contents)
+        .map_err(|e| format!("{:?}", e))
+}
+
+/// The current time as seconds since the Unix epoch.
+pub fn unix_timestamp() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default()
+    }
+}
+
+/// Formats a Unix timestamp as a `YYYY-MM-DD` date in UTC.
+pub fn format_date(timestamp: u64) -> String {
+    // Civil from days algorithm: http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = timestamp / 86_400 + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as u64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_date_starts_at_the_epoch() {
+        assert_eq!(format_date(0), "1970-01-01");
+    }
+
+    #[test]
+    fn format_date_handles_leap_days() {
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(951_868_800), "2000-03-01");
+        assert_eq!(format_date(1_709_164_800), "2024-02-29");
+        assert_eq!(format_date(1_709_251_199), "2024-02-29");
+    }
+
+    #[test]
+    fn format_date_skips_leap_days_in_non_leap_centuries() {
+        assert_eq!(format_date(4_107_456_000), "2100-02-28");
+        assert_eq!(format_date(4_107_542_400), "2100-03-01");
+    }
+}
@@ -0,0 +1,437 @@
+use bevy::prelude::*;
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    reflect::TypePath,
+    utils::BoxedFuture,
+};
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppState,
+    assets::{self, GameAssets, RonAssetLoaderError},
+    atlas::SpriteAtlas,
+    game::{BirdScored, GameData, GameState},
+    menu::MenuScreen,
+    scrolling::ScrollingMaterial,
+    storage,
+    touch::UiInput,
+};
+
+const THEME_FILENAME: &str = "theme.ron";
+/// Choices for how often themes rotate during a run. Zero disables rotation.
+const ROTATE_EVERY_CHOICES: [u16; 4] = [0, 5, 10, 25];
+
+pub struct ThemesPlugin;
+
+impl Plugin for ThemesPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(storage::load::<ThemeSettings>(THEME_FILENAME))
+            .init_resource::<Themes>()
+            .init_resource::<ActiveTheme>()
+            .add_systems(OnExit(AppState::Loading), build_themes)
+            // Every run starts on the selected theme.
+            .add_systems(OnEnter(GameState::Ready), reset_active_theme)
+            .add_systems(OnExit(AppState::InGame), reset_active_theme)
+            .add_systems(Update, rotate_theme
+                .run_if(in_state(AppState::InGame))
+                .run_if(in_state(GameState::Playing)))
+            .add_systems(Update, themes_screen_ui
+                .run_if(in_state(AppState::MainMenu))
+                .run_if(resource_equals(MenuScreen::Themes)))
+            .add_systems(PostUpdate, apply_theme
+                .run_if(not(in_state(AppState::Loading)))
+                .run_if(resource_changed::<ActiveTheme>()));
+    }
+}
+
+#[derive(Deserialize)]
+struct ThemeListFile {
+    themes: Vec<ThemeFile>,
+}
+
+#[derive(Deserialize)]
+struct ThemeFile {
+    name: String,
+    /// sRGB color shown behind everything else.
+    clear_color: (u8, u8, u8),
+    /// Path to the background image, relative to the theme list.
+    background: String,
+    /// Path to a `.atlas.ron` manifest defining every region in `region`, relative to the theme
+    /// list.
+    terrain: String,
+    #[serde(default)]
+    parallax: Option<ParallaxFile>,
+}
+
+#[derive(Deserialize)]
+struct ParallaxFile {
+    /// Path to a `.atlas.ron` manifest defining every layer's region, relative to the theme list.
+    atlas: String,
+    layers: Vec<ParallaxLayerDef>,
+}
+
+#[derive(Deserialize)]
+pub struct ParallaxLayerDef {
+    pub region: String,
+    /// How fast the layer scrolls compared to the pipes.
+    pub factor: f32,
+    pub z: f32,
+}
+
+pub struct ThemeDef {
+    pub name: String,
+    pub clear_color: Color,
+    pub background: Handle<Image>,
+    pub terrain: Handle<SpriteAtlas>,
+    pub parallax: Option<(Handle<SpriteAtlas>, Vec<ParallaxLayerDef>)>,
+}
+
+/// Theme definitions, loaded from a `.themes.ron` file. Images and atlases they reference are
+/// loaded as dependencies.
+#[derive(Asset, TypePath)]
+pub struct ThemeList {
+    pub themes: Vec<ThemeDef>,
+}
+
+#[derive(Default)]
+pub struct ThemeListLoader;
+
+impl AssetLoader for ThemeListLoader {
+    type Asset = ThemeList;
+    type Settings = ();
+    type Error = RonAssetLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let file: ThemeListFile = ron::de::from_bytes(&bytes)?;
+
+            let themes = file.themes.into_iter()
+                .map(|theme| {
+                    let (r, g, b) = theme.clear_color;
+                    ThemeDef {
+                        name: theme.name,
+                        clear_color: Color::rgb_u8(r, g, b),
+                        background: load_context.load(assets::relative_path(load_context, &theme.background)),
+                        terrain: load_context.load(assets::relative_path(load_context, &theme.terrain)),
+                        parallax: theme.parallax.map(|parallax| {
+                            let atlas = load_context.load(assets::relative_path(load_context, &parallax.atlas));
+                            (atlas, parallax.layers)
+                        }),
+                    }
+                })
+                .collect();
+            Ok(ThemeList { themes })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["themes.ron"]
+    }
+}
+
+/// A theme ready to be used by game entities.
+pub struct Theme {
+    pub name: String,
+    pub clear_color: Color,
+    pub background: Handle<Image>,
+    pub terrain_atlas: Handle<TextureAtlas>,
+    pub pipe_top: usize,
+    pub pipe_bottom: usize,
+    /// Tiling textures cut out of the terrain image.
+    pub pipe_center: Handle<Image>,
+    pub ground_top: Handle<Image>,
+    pub ground: Handle<Image>,
+    /// Drawn over the background, back to front.
+    pub layers: Vec<ParallaxLayer>,
+}
+
+/// A band of an image that scrolls behind the pipes.
+pub struct ParallaxLayer {
+    pub texture: Handle<Image>,
+    /// Part of `texture` to draw, in pixels.
+    pub rect: Rect,
+    pub factor: f32,
+    pub z: f32,
+}
+
+#[derive(Default, Resource)]
+pub struct Themes(pub Vec<Theme>);
+
+impl Themes {
+    /// The theme at `index`, or the first theme if there's no such theme. `None` if no themes could
+    /// be built.
+    pub fn get(&self, index: usize) -> Option<&Theme> {
+        self.0.get(index).or_else(|| self.0.first())
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.0.iter().position(|theme| theme.name == name)
+    }
+}
+
+/// Index into `Themes` of the theme currently shown.
+#[derive(Default, PartialEq, Resource)]
+pub struct ActiveTheme(pub usize);
+
+#[derive(Default, Deserialize, Serialize, Resource)]
+#[serde(default)]
+pub struct ThemeSettings {
+    /// Name of the theme runs start with. Falls back to the first theme if it doesn't exist.
+    pub selected: String,
+    /// Switch to the next theme every this many points. Zero disables rotation.
+    pub rotate_every: u16,
+}
+
+impl ThemeSettings {
+    fn save(&self) {
+        storage::save(THEME_FILENAME, self);
+    }
+}
+
+/// Which part of a theme an entity displays, so it can be updated when the theme changes.
+#[derive(Clone, Copy, Component)]
+pub enum ThemePart {
+    Ground,
+    GroundTop,
+    PipeBody,
+    PipeTop,
+    PipeBottom,
+    /// The background or one of the parallax layers over it. These are rebuilt rather than
+    /// updated when the theme changes.
+    Parallax,
+}
+
+/// Names of the regions every terrain atlas must define. The pipe center and ground regions are
+/// tiled, so they must also be listed in the atlas's `tiles`.
+mod region {
+    pub const PIPE_TOP: &str = "pipe_top";
+    pub const PIPE_BOTTOM: &str = "pipe_bottom";
+    pub const PIPE_CENTER: &str = "pipe_center";
+    pub const GROUND_TOP: &str = "ground_top";
+    pub const GROUND: &str = "ground";
+}
+
+fn build_layers(
+    def: &ThemeDef,
+    sprite_atlases: &Assets<SpriteAtlas>,
+    texture_atlases: &Assets<TextureAtlas>,
+) -> Vec<ParallaxLayer> {
+    let Some((atlas_handle, layer_defs)) = &def.parallax else {
+        return Vec::new();
+    };
+    let sprite_atlas = sprite_atlases.get(atlas_handle);
+    let texture_atlas = sprite_atlas.and_then(|sprite_atlas| texture_atlases.get(&sprite_atlas.atlas));
+    let (Some(sprite_atlas), Some(texture_atlas)) = (sprite_atlas, texture_atlas) else {
+        error!("Parallax atlas for theme {} isn't loaded", def.name);
+        return Vec::new();
+    };
+
+    let mut layers: Vec<_> = layer_defs.iter()
+        .filter_map(|layer| {
+            let Some(index) = sprite_atlas.index(&layer.region) else {
+                error!("Parallax atlas for theme {} has no {} region", def.name, layer.region);
+                return None;
+            };
+            Some(ParallaxLayer {
+                texture: texture_atlas.texture.clone(),
+                rect: texture_atlas.textures[index],
+                factor: layer.factor,
+                z: layer.z,
+            })
+        })
+        .collect();
+    layers.sort_by(|a, b| a.z.total_cmp(&b.z));
+    layers
+}
+
+fn build_theme(
+    def: &ThemeDef,
+    sprite_atlases: &Assets<SpriteAtlas>,
+    texture_atlases: &Assets<TextureAtlas>,
+) -> Option<Theme> {
+    let Some(terrain) = sprite_atlases.get(&def.terrain) else {
+        error!("Terrain atlas for theme {} isn't loaded", def.name);
+        return None;
+    };
+
+    let index = |name: &str| {
+        let index = terrain.index(name);
+        if index.is_none() {
+            error!("Terrain atlas for theme {} has no {} region", def.name, name);
+        }
+        index
+    };
+    let tile = |name: &str| {
+        let tile = terrain.tile(name);
+        if tile.is_none() {
+            error!("Terrain atlas for theme {} has no {} tile", def.name, name);
+        }
+        tile
+    };
+
+    Some(Theme {
+        name: def.name.clone(),
+        clear_color: def.clear_color,
+        background: def.background.clone(),
+        terrain_atlas: terrain.atlas.clone(),
+        pipe_top: index(region::PIPE_TOP)?,
+        pipe_bottom: index(region::PIPE_BOTTOM)?,
+        pipe_center: tile(region::PIPE_CENTER)?,
+        ground_top: tile(region::GROUND_TOP)?,
+        ground: tile(region::GROUND)?,
+        layers: build_layers(def, sprite_atlases, texture_atlases),
+    })
+}
+
+fn build_themes(
+    assets: Res<GameAssets>,
+    theme_lists: Res<Assets<ThemeList>>,
+    settings: Res<ThemeSettings>,
+    mut themes: ResMut<Themes>,
+    mut active_theme: ResMut<ActiveTheme>,
+    sprite_atlases: Res<Assets<SpriteAtlas>>,
+    texture_atlases: Res<Assets<TextureAtlas>>,
+) {
+    let Some(theme_list) = theme_lists.get(&assets.themes) else {
+        error!("Theme list isn't loaded");
+        return;
+    };
+
+    themes.0 = theme_list.themes.iter()
+        .filter_map(|def| build_theme(def, &sprite_atlases, &texture_atlases))
+        .collect();
+    active_theme.0 = themes.index_of(&settings.selected).unwrap_or(0);
+}
+
+fn reset_active_theme(
+    themes: Res<Themes>,
+    settings: Res<ThemeSettings>,
+    mut active_theme: ResMut<ActiveTheme>,
+) {
+    active_theme.set_if_neq(ActiveTheme(themes.index_of(&settings.selected).unwrap_or(0)));
+}
+
+fn rotate_theme(
+    mut scored: EventReader<BirdScored>,
+    game_data: Res<GameData>,
+    settings: Res<ThemeSettings>,
+    themes: Res<Themes>,
+    mut active_theme: ResMut<ActiveTheme>,
+) {
+    if scored.read().count() == 0 || settings.rotate_every == 0 || themes.0.is_empty() {
+        return;
+    }
+
+    if game_data.score % settings.rotate_every == 0 {
+        active_theme.0 = (active_theme.0 + 1) % themes.0.len();
+    }
+}
+
+pub fn apply_theme(
+    themes: Res<Themes>,
+    active_theme: Res<ActiveTheme>,
+    mut clear_color: ResMut<ClearColor>,
+    mut materials: ResMut<Assets<ScrollingMaterial>>,
+    mut image_q: Query<(&ThemePart, &mut Handle<Image>)>,
+    mut atlas_q: Query<(&ThemePart, &mut Handle<TextureAtlas>, &mut TextureAtlasSprite)>,
+    material_q: Query<(&ThemePart, &Handle<ScrollingMaterial>)>,
+) {
+    let Some(theme) = themes.get(active_theme.0) else {
+        return;
+    };
+    debug!("Applying theme {}", theme.name);
+
+    clear_color.0 = theme.clear_color;
+
+    for (part, mut texture) in image_q.iter_mut() {
+        if let ThemePart::PipeBody = part {
+            *texture = theme.pipe_center.clone();
+        }
+    }
+
+    for (part, mut atlas, mut sprite) in atlas_q.iter_mut() {
+        let index = match part {
+            ThemePart::PipeTop => theme.pipe_top,
+            ThemePart::PipeBottom => theme.pipe_bottom,
+            _ => continue,
+        };
+        *atlas = theme.terrain_atlas.clone();
+        sprite.index = index;
+    }
+
+    for (part, material_handle) in material_q.iter() {
+        let texture = match part {
+            ThemePart::Ground => theme.ground.clone(),
+            ThemePart::GroundTop => theme.ground_top.clone(),
+            _ => continue,
+        };
+        if let Some(material) = materials.get_mut(material_handle) {
+            material.texture = texture;
+        }
+    }
+}
+
+fn themes_screen_ui(
+    input: UiInput,
+    themes: Res<Themes>,
+    mut settings: ResMut<ThemeSettings>,
+    mut active_theme: ResMut<ActiveTheme>,
+    mut menu_screen: ResMut<MenuScreen>,
+    mut egui_ctx: EguiContexts,
+) {
+    let window = egui::Window::new("Themes")
+        .title_bar(false)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -50.0]);
+    window.show(egui_ctx.ctx_mut(), |ui| {
+        ui.set_width(250.0);
+        ui.vertical_centered_justified(|ui| {
+            ui.label(egui::RichText::new("Themes").size(30.0));
+
+            for (i, theme) in themes.0.iter().enumerate() {
+                let is_selected = active_theme.0 == i;
+                let response = ui.selectable_label(is_selected, egui::RichText::new(&theme.name).size(24.0));
+                let picked = response.clicked() || input.just_touched(response.rect);
+                if picked && !is_selected {
+                    settings.selected = theme.name.clone();
+                    settings.save();
+                    active_theme.0 = i;
+                }
+            }
+
+            ui.add_space(10.0);
+            ui.label(egui::RichText::new("Change theme every").size(20.0));
+            ui.horizontal(|ui| {
+                for choice in ROTATE_EVERY_CHOICES {
+                    let text = match choice {
+                        0 => "Never".to_string(),
+                        points => points.to_string(),
+                    };
+                    let is_selected = settings.rotate_every == choice;
+                    let response = ui.selectable_label(is_selected, egui::RichText::new(text).size(20.0));
+                    let picked = response.clicked() || input.just_touched(response.rect);
+                    if picked && !is_selected {
+                        settings.rotate_every = choice;
+                        settings.save();
+                    }
+                }
+            });
+
+            ui.add_space(10.0);
+            let back = ui.button(egui::RichText::new("Back").size(30.0));
+            if back.clicked() || input.just_touched(back.rect) {
+                *menu_screen = MenuScreen::Main;
+            }
+        });
+    });
+}
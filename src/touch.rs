@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy_egui::{egui, EguiSettings};
+
+/// The input egui screens read for keyboard navigation and touches.
+#[derive(SystemParam)]
+pub struct UiInput<'w> {
+    pub keys: Res<'w, Input<KeyCode>>,
+    pub touches: Res<'w, Touches>,
+    pub egui_settings: Res<'w, EguiSettings>,
+}
+
+impl UiInput<'_> {
+    /// Returns true if a touch started inside the given egui rect this frame.
+    pub fn just_touched(&self, rect: egui::Rect) -> bool {
+        just_touched(&self.touches, &self.egui_settings, rect)
+    }
+}
+
+/// Returns true if a touch started inside the given egui rect this frame.
+///
+/// bevy_egui doesn't forward touches, so widgets that need to respond to touch hit test them
+/// manually.
+pub fn just_touched(touches: &Touches, egui_settings: &EguiSettings, rect: egui::Rect) -> bool {
+    let scale = egui_settings.scale_factor as f32;
+    touches.iter_just_pressed().any(|touch| {
+        let pos = touch.position() / scale;
+        rect.contains(egui::pos2(pos.x, pos.y))
+    })
+}
+
+/// Returns true if a touch started outside every egui area this frame, so it didn't land on any
+/// widget.
+pub fn just_touched_outside(touches: &Touches, egui_settings: &EguiSettings, ctx: &egui::Context) -> bool {
+    let scale = egui_settings.scale_factor as f32;
+    touches.iter_just_pressed().any(|touch| {
+        let pos = touch.position() / scale;
+        ctx.layer_id_at(egui::pos2(pos.x, pos.y)).is_none()
+    })
+}
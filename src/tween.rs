@@ -0,0 +1,547 @@
+//! Animates component values over time.
+//!
+//! Add an `Animator<T>` holding a tweenable to an entity to animate its `T` component. Animators
+//! remove themselves once they complete, so they only write to the component while running.
+//! Animators for components other than `Transform`, `Sprite` and `Text` need
+//! `animate_component::<T>` added in `TweenSystems`.
+
+use std::{f32::consts::PI, time::Duration};
+
+use bevy::prelude::*;
+use bevy::reflect::GetPath;
+use serde::Deserialize;
+
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<TweenCompleted>()
+            .add_systems(Update, (
+                animate_component::<Transform>,
+                animate_component::<Sprite>,
+                animate_component::<Text>,
+            ).in_set(TweenSystems));
+    }
+}
+
+/// Systems that apply animators to their components.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct TweenSystems;
+
+/// Sent when an animator given a completion id finishes.
+#[derive(Debug, Clone, Event)]
+pub struct TweenCompleted {
+    pub entity: Entity,
+    pub id: u64,
+}
+
+/// Easing curves, which can also be named in data files. See https://easings.net for what they
+/// look like.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Ease {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineInOut,
+    BackOut,
+    ElasticOut,
+    BounceOut,
+}
+
+impl Ease {
+    /// Maps linear progress from 0 to 1 onto the curve.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::QuadIn => t * t,
+            Self::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Self::QuadInOut => if t < 0.5 {
+                2.0 * t * t
+            } else {
+                1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+            },
+            Self::CubicIn => t.powi(3),
+            Self::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Self::CubicInOut => if t < 0.5 {
+                4.0 * t.powi(3)
+            } else {
+                1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+            },
+            Self::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Self::BackOut => {
+                const C1: f32 = 1.70158;
+                const C3: f32 = C1 + 1.0;
+                1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+            }
+            Self::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    return t;
+                }
+                const C4: f32 = 2.0 * PI / 3.0;
+                2.0_f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * C4).sin() + 1.0
+            }
+            Self::BounceOut => {
+                const N1: f32 = 7.5625;
+                const D1: f32 = 2.75;
+                if t < 1.0 / D1 {
+                    N1 * t * t
+                } else if t < 2.0 / D1 {
+                    let t = t - 1.5 / D1;
+                    N1 * t * t + 0.75
+                } else if t < 2.5 / D1 {
+                    let t = t - 2.25 / D1;
+                    N1 * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / D1;
+                    N1 * t * t + 0.984375
+                }
+            }
+        }
+    }
+}
+
+/// Values that can be blended between a start and an end.
+pub trait Interpolate: Reflect + Copy {
+    fn interpolate(start: Self, end: Self, ratio: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(start: Self, end: Self, ratio: f32) -> Self {
+        start + (end - start) * ratio
+    }
+}
+
+impl Interpolate for Vec2 {
+    fn interpolate(start: Self, end: Self, ratio: f32) -> Self {
+        start.lerp(end, ratio)
+    }
+}
+
+impl Interpolate for Vec3 {
+    fn interpolate(start: Self, end: Self, ratio: f32) -> Self {
+        start.lerp(end, ratio)
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(start: Self, end: Self, ratio: f32) -> Self {
+        let start = Vec4::from(start.as_rgba_f32());
+        let end = Vec4::from(end.as_rgba_f32());
+        let mixed = start.lerp(end, ratio);
+        Color::rgba(mixed.x, mixed.y, mixed.z, mixed.w)
+    }
+}
+
+/// Writes a value blended by an eased ratio into part of a component.
+pub trait Lens<T>: Send + Sync + 'static {
+    fn lerp(&mut self, target: &mut T, ratio: f32);
+}
+
+pub struct TransformPositionLens {
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+impl Lens<Transform> for TransformPositionLens {
+    fn lerp(&mut self, target: &mut Transform, ratio: f32) {
+        target.translation = Vec3::interpolate(self.start, self.end, ratio);
+    }
+}
+
+pub struct TransformScaleLens {
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+impl Lens<Transform> for TransformScaleLens {
+    fn lerp(&mut self, target: &mut Transform, ratio: f32) {
+        target.scale = Vec3::interpolate(self.start, self.end, ratio);
+    }
+}
+
+pub struct SpriteColorLens {
+    pub start: Color,
+    pub end: Color,
+}
+
+impl Lens<Sprite> for SpriteColorLens {
+    fn lerp(&mut self, target: &mut Sprite, ratio: f32) {
+        target.color = Color::interpolate(self.start, self.end, ratio);
+    }
+}
+
+pub struct TextColorLens {
+    pub section: usize,
+    pub start: Color,
+    pub end: Color,
+}
+
+impl Lens<Text> for TextColorLens {
+    fn lerp(&mut self, target: &mut Text, ratio: f32) {
+        if let Some(section) = target.sections.get_mut(self.section) {
+            section.style.color = Color::interpolate(self.start, self.end, ratio);
+        }
+    }
+}
+
+/// Animates any reflected field of a component by its path, like `"translation.y"`.
+pub struct ReflectLens<V> {
+    pub path: String,
+    pub start: V,
+    pub end: V,
+}
+
+impl<T: Reflect, V: Interpolate> Lens<T> for ReflectLens<V> {
+    fn lerp(&mut self, target: &mut T, ratio: f32) {
+        match target.path_mut::<V>(self.path.as_str()) {
+            Ok(value) => *value = V::interpolate(self.start, self.end, ratio),
+            Err(err) => warn!("Can't tween {}: {}", self.path, err),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatCount {
+    /// Play this many times in total.
+    Finite(u32),
+    Infinite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TweenState {
+    Active,
+    /// Finished with this much of the last tick left over.
+    Completed(Duration),
+}
+
+/// Something that can be played over time to animate a `T`.
+pub trait Tweenable<T>: Send + Sync + 'static {
+    /// Advances by `delta`, writing to `target`.
+    fn tick(&mut self, delta: Duration, target: &mut T) -> TweenState;
+}
+
+/// Blends a lens from its start to its end over a duration.
+pub struct Tween<T> {
+    ease: Ease,
+    duration: Duration,
+    lens: Box<dyn Lens<T>>,
+    repeat: RepeatCount,
+    /// Play every other repeat backwards.
+    yoyo: bool,
+    elapsed: Duration,
+    plays: u32,
+    done: bool,
+}
+
+impl<T: 'static> Tween<T> {
+    pub fn new(ease: Ease, duration: Duration, lens: impl Lens<T>) -> Self {
+        Self {
+            ease,
+            duration,
+            lens: Box::new(lens),
+            repeat: RepeatCount::Finite(1),
+            yoyo: false,
+            elapsed: Duration::ZERO,
+            plays: 0,
+            done: false,
+        }
+    }
+
+    pub fn with_repeat(mut self, repeat: RepeatCount) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn with_yoyo(mut self, yoyo: bool) -> Self {
+        self.yoyo = yoyo;
+        self
+    }
+
+    fn apply(&mut self, target: &mut T, progress: f32) {
+        let backwards = self.yoyo && self.plays % 2 == 1;
+        let progress = if backwards { 1.0 - progress } else { progress };
+        self.lens.lerp(target, self.ease.apply(progress));
+    }
+
+    fn finish(&mut self, left_over: Duration) -> TweenState {
+        self.done = true;
+        TweenState::Completed(left_over)
+    }
+}
+
+impl<T: Send + Sync + 'static> Tweenable<T> for Tween<T> {
+    fn tick(&mut self, delta: Duration, target: &mut T) -> TweenState {
+        if self.done {
+            return TweenState::Completed(delta);
+        }
+
+        if self.duration.is_zero() {
+            self.apply(target, 1.0);
+            return self.finish(delta);
+        }
+
+        self.elapsed += delta;
+        while self.elapsed >= self.duration {
+            self.elapsed -= self.duration;
+            if let RepeatCount::Finite(times) = self.repeat {
+                if self.plays + 1 >= times {
+                    // End exactly where the last play ends.
+                    self.apply(target, 1.0);
+                    let left_over = self.elapsed;
+                    return self.finish(left_over);
+                }
+            }
+            self.plays += 1;
+        }
+
+        let progress = self.elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        self.apply(target, progress);
+        TweenState::Active
+    }
+}
+
+/// Waits before letting the next tweenable in a sequence start.
+pub struct Delay {
+    duration: Duration,
+    elapsed: Duration,
+}
+
+impl Delay {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+impl<T> Tweenable<T> for Delay {
+    fn tick(&mut self, delta: Duration, _target: &mut T) -> TweenState {
+        self.elapsed += delta;
+        if self.elapsed < self.duration {
+            return TweenState::Active;
+        }
+        TweenState::Completed(self.elapsed - self.duration)
+    }
+}
+
+/// Plays tweenables one after another.
+pub struct Sequence<T> {
+    tweenables: Vec<Box<dyn Tweenable<T>>>,
+    index: usize,
+}
+
+impl<T: 'static> Sequence<T> {
+    pub fn new(first: impl Tweenable<T>) -> Self {
+        Self {
+            tweenables: vec![Box::new(first)],
+            index: 0,
+        }
+    }
+
+    pub fn then(mut self, tweenable: impl Tweenable<T>) -> Self {
+        self.tweenables.push(Box::new(tweenable));
+        self
+    }
+}
+
+impl<T: 'static> Tweenable<T> for Sequence<T> {
+    fn tick(&mut self, delta: Duration, target: &mut T) -> TweenState {
+        let mut delta = delta;
+        while let Some(tweenable) = self.tweenables.get_mut(self.index) {
+            match tweenable.tick(delta, target) {
+                TweenState::Active => return TweenState::Active,
+                TweenState::Completed(left_over) => {
+                    self.index += 1;
+                    delta = left_over;
+                }
+            }
+        }
+        TweenState::Completed(delta)
+    }
+}
+
+/// Plays tweenables at the same time, finishing when all of them have.
+pub struct Parallel<T> {
+    tweenables: Vec<Box<dyn Tweenable<T>>>,
+}
+
+impl<T: 'static> Parallel<T> {
+    pub fn new(first: impl Tweenable<T>) -> Self {
+        Self {
+            tweenables: vec![Box::new(first)],
+        }
+    }
+
+    pub fn with(mut self, tweenable: impl Tweenable<T>) -> Self {
+        self.tweenables.push(Box::new(tweenable));
+        self
+    }
+}
+
+impl<T: 'static> Tweenable<T> for Parallel<T> {
+    fn tick(&mut self, delta: Duration, target: &mut T) -> TweenState {
+        let mut left_over = delta;
+        let mut all_done = true;
+        for tweenable in &mut self.tweenables {
+            match tweenable.tick(delta, target) {
+                TweenState::Active => all_done = false,
+                TweenState::Completed(child_left_over) => left_over = left_over.min(child_left_over),
+            }
+        }
+
+        if !all_done {
+            return TweenState::Active;
+        }
+        TweenState::Completed(left_over)
+    }
+}
+
+/// Plays a tweenable on the entity's `T` component.
+#[derive(Component)]
+pub struct Animator<T: Component> {
+    tweenable: Box<dyn Tweenable<T>>,
+    completed_id: Option<u64>,
+}
+
+impl<T: Component> Animator<T> {
+    pub fn new(tweenable: impl Tweenable<T>) -> Self {
+        Self {
+            tweenable: Box::new(tweenable),
+            completed_id: None,
+        }
+    }
+
+    /// Sends a `TweenCompleted` with this id when the tweenable finishes.
+    pub fn with_completed_id(mut self, id: u64) -> Self {
+        self.completed_id = Some(id);
+        self
+    }
+}
+
+pub fn animate_component<T: Component>(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut completed_events: EventWriter<TweenCompleted>,
+    mut animator_q: Query<(Entity, &mut T, &mut Animator<T>)>,
+) {
+    for (entity, mut target, mut animator) in animator_q.iter_mut() {
+        let state = animator.tweenable.tick(time.delta(), target.as_mut());
+
+        // Stop touching the component once the animation is over.
+        if let TweenState::Completed(_) = state {
+            if let Some(id) = animator.completed_id {
+                completed_events.send(TweenCompleted { entity, id });
+            }
+            commands.entity(entity).remove::<Animator<T>>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the eased ratio straight into an `f32`.
+    struct RatioLens;
+
+    impl Lens<f32> for RatioLens {
+        fn lerp(&mut self, target: &mut f32, ratio: f32) {
+            *target = ratio;
+        }
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn linear(millis: u64) -> Tween<f32> {
+        Tween::new(Ease::Linear, ms(millis), RatioLens)
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-4, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn ease_curves_start_at_zero_and_end_at_one() {
+        let eases = [
+            Ease::Linear, Ease::QuadIn, Ease::QuadOut, Ease::QuadInOut, Ease::CubicIn, Ease::CubicOut,
+            Ease::CubicInOut, Ease::SineInOut, Ease::BackOut, Ease::ElasticOut, Ease::BounceOut,
+        ];
+        for ease in eases {
+            assert_near(ease.apply(0.0), 0.0);
+            assert_near(ease.apply(1.0), 1.0);
+        }
+    }
+
+    #[test]
+    fn ease_clamps_progress() {
+        assert_near(Ease::QuadIn.apply(-1.0), 0.0);
+        assert_near(Ease::QuadIn.apply(2.0), 1.0);
+    }
+
+    #[test]
+    fn tween_ends_exactly_at_the_end() {
+        let mut value = 0.0;
+        let mut tween = linear(100);
+        assert_eq!(tween.tick(ms(25), &mut value), TweenState::Active);
+        assert_near(value, 0.25);
+        assert_eq!(tween.tick(ms(125), &mut value), TweenState::Completed(ms(50)));
+        assert_near(value, 1.0);
+    }
+
+    #[test]
+    fn yoyo_plays_every_other_repeat_backwards() {
+        let mut value = 0.0;
+        let mut tween = linear(100)
+            .with_repeat(RepeatCount::Finite(2))
+            .with_yoyo(true);
+        tween.tick(ms(25), &mut value);
+        assert_near(value, 0.25);
+        assert_eq!(tween.tick(ms(100), &mut value), TweenState::Active);
+        assert_near(value, 0.75);
+        assert_eq!(tween.tick(ms(75), &mut value), TweenState::Completed(Duration::ZERO));
+        assert_near(value, 0.0);
+    }
+
+    #[test]
+    fn infinite_yoyo_keeps_turning_around() {
+        let mut value = 0.0;
+        let mut tween = linear(100)
+            .with_repeat(RepeatCount::Infinite)
+            .with_yoyo(true);
+        assert_eq!(tween.tick(ms(330), &mut value), TweenState::Active);
+        assert_near(value, 0.7);
+        assert_eq!(tween.tick(ms(100), &mut value), TweenState::Active);
+        assert_near(value, 0.3);
+    }
+
+    #[test]
+    fn sequence_waits_for_delays() {
+        let mut value = -1.0;
+        let mut sequence = Sequence::new(Delay::new(ms(100))).then(linear(100));
+        assert_eq!(sequence.tick(ms(50), &mut value), TweenState::Active);
+        assert_near(value, -1.0);
+        assert_eq!(sequence.tick(ms(100), &mut value), TweenState::Active);
+        assert_near(value, 0.5);
+        assert_eq!(sequence.tick(ms(100), &mut value), TweenState::Completed(ms(50)));
+        assert_near(value, 1.0);
+    }
+
+    #[test]
+    fn parallel_finishes_with_its_longest_tweenable() {
+        let mut value = 0.0;
+        let mut parallel = Parallel::new(linear(100)).with(Delay::new(ms(200)));
+        assert_eq!(parallel.tick(ms(150), &mut value), TweenState::Active);
+        assert_near(value, 1.0);
+        assert_eq!(parallel.tick(ms(100), &mut value), TweenState::Completed(ms(50)));
+    }
+}
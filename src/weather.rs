@@ -0,0 +1,337 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GAME_SIZE, AppState,
+    assets::GameAssets,
+    camera::PlayField,
+    game::{BirdScored, GameData, GameState},
+    particles::ParticleEmitter,
+    storage,
+    tween::{Animator, Delay, Ease, Sequence, TextColorLens, Tween, TweenCompleted},
+};
+
+const WEATHER_FILENAME: &str = "weather.ron";
+/// Roll new weather every this many points.
+const CHANGE_EVERY: u16 = 5;
+/// Mixed into the run's seed so weather rolls don't line up with pipe heights.
+const SEED_SALT: u64 = 0x5745_4154_4845_5221;
+/// Seconds between the peaks of wind gusts.
+const GUST_PERIOD: f32 = 3.0;
+const HEAVY_RAIN_GRAVITY_SCALE: f32 = 1.15;
+const WEATHER_Z: f32 = 12.0;
+const ANNOUNCEMENT_FONT_SIZE: f32 = 16.0;
+/// Transparent white, so announcements don't darken as they fade.
+const ANNOUNCEMENT_HIDDEN: Color = Color::rgba(1.0, 1.0, 1.0, 0.0);
+/// Completion id of an announcement's fade out.
+const ANNOUNCEMENT_DONE: u64 = 2;
+
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(storage::load::<WeatherSettings>(WEATHER_FILENAME))
+            .init_resource::<WeatherState>()
+            .init_resource::<WeatherForces>()
+            .add_systems(OnEnter(GameState::Ready), reset_weather)
+            .add_systems(OnEnter(GameState::Playing), seed_weather)
+            .add_systems(OnExit(AppState::InGame), teardown_weather)
+            .add_systems(Update, (
+                change_weather.run_if(in_state(GameState::Playing)),
+                update_weather_forces.after(change_weather),
+                fit_to_play_field.run_if(resource_changed::<PlayField>()),
+                despawn_announcements,
+            ).run_if(in_state(AppState::InGame)));
+    }
+}
+
+#[derive(Deserialize, Serialize, Resource)]
+#[serde(default)]
+pub struct WeatherSettings {
+    /// Whether wind and heavy rain push the bird around, or are only for show.
+    pub affects_flight: bool,
+}
+
+impl Default for WeatherSettings {
+    fn default() -> Self {
+        Self {
+            affects_flight: true,
+        }
+    }
+}
+
+impl WeatherSettings {
+    pub fn save(&self) {
+        storage::save(WEATHER_FILENAME, self);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum Weather {
+    #[default]
+    Clear,
+    Rain {
+        heavy: bool,
+    },
+    Snow,
+    /// Gusts that push the bird by `force` at their peak.
+    Wind {
+        force: Vec2,
+    },
+}
+
+impl Weather {
+    fn roll(rng: &mut fastrand::Rng) -> Self {
+        match rng.u32(..100) {
+            0..=39 => Self::Clear,
+            40..=59 => Self::Rain { heavy: rng.bool() },
+            60..=74 => Self::Snow,
+            _ => {
+                // Sideways wind moves the bird directly, while updrafts and downdrafts fight
+                // gravity, so they need to be a lot stronger to be felt.
+                let force = match rng.u32(..4) {
+                    0 => Vec2::new(-(25.0 + rng.f32() * 15.0), 0.0),
+                    1 => Vec2::new(25.0 + rng.f32() * 15.0, 0.0),
+                    2 => Vec2::new(0.0, 120.0 + rng.f32() * 80.0),
+                    _ => Vec2::new(0.0, -(120.0 + rng.f32() * 80.0)),
+                };
+                Self::Wind { force }
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Clear => "Clear skies",
+            Self::Rain { heavy: false } => "Rain",
+            Self::Rain { heavy: true } => "Heavy rain",
+            Self::Snow => "Snow",
+            Self::Wind { force } if force.x < 0.0 => "Headwind",
+            Self::Wind { force } if force.x > 0.0 => "Tailwind",
+            Self::Wind { force } if force.y > 0.0 => "Updrafts",
+            Self::Wind { .. } => "Downdrafts",
+        }
+    }
+
+    /// Particle preset shown for this weather, and the angle to turn it by.
+    fn particles(&self) -> Option<(&'static str, f32)> {
+        match self {
+            Self::Clear => None,
+            Self::Rain { heavy: false } => Some(("rain", 0.0)),
+            Self::Rain { heavy: true } => Some(("heavy_rain", 0.0)),
+            Self::Snow => Some(("snow", 0.0)),
+            Self::Wind { force } => Some(("wind", force.y.atan2(force.x).to_degrees())),
+        }
+    }
+}
+
+/// The current run's weather. Rolls come from the run's seed, so replaying a seed replays its
+/// weather too.
+#[derive(Default, Resource)]
+struct WeatherState {
+    weather: Weather,
+    /// Seconds since the weather last changed.
+    elapsed: f32,
+    rng: fastrand::Rng,
+}
+
+/// How the weather is pushing on the bird right now.
+#[derive(Resource)]
+pub struct WeatherForces {
+    pub gravity_scale: f32,
+    /// Pixels per second sideways, and pixels per second squared vertically.
+    pub wind: Vec2,
+}
+
+impl Default for WeatherForces {
+    fn default() -> Self {
+        Self {
+            gravity_scale: 1.0,
+            wind: Vec2::ZERO,
+        }
+    }
+}
+
+/// Emits the current weather's particles.
+#[derive(Component)]
+struct WeatherEmitter;
+
+/// Spawns emitters for the weather's particles. Presets cover the default play field's width, so
+/// wider play fields get more emitters side by side.
+fn spawn_emitters(commands: &mut Commands, weather: Weather, play_field: &PlayField) {
+    let Some((preset, angle)) = weather.particles() else {
+        return;
+    };
+
+    let columns = (play_field.width / GAME_SIZE.0).ceil().max(1.0);
+    let column_width = play_field.width / columns;
+    for i in 0..columns as usize {
+        let x = (i as f32 + 0.5) * column_width;
+        // Wind blows across the whole screen, everything else falls from the top.
+        let position = match weather {
+            Weather::Wind { .. } => Vec3::new(x, GAME_SIZE.1 / 2.0, WEATHER_Z),
+            _ => Vec3::new(x, GAME_SIZE.1 + 8.0, WEATHER_Z),
+        };
+        commands.spawn(TransformBundle::from_transform(Transform::from_translation(position)))
+            .insert(Name::new("Weather"))
+            .insert(WeatherEmitter)
+            .insert(ParticleEmitter::new(preset, Duration::MAX).with_angle(angle));
+    }
+}
+
+/// On-screen text naming the new weather.
+#[derive(Component)]
+struct WeatherAnnouncement;
+
+/// Everything spawned for the current weather.
+type WeatherEntityQuery<'w, 's> = Query<'w, 's, Entity, Or<(With<WeatherEmitter>, With<WeatherAnnouncement>)>>;
+
+fn reset_weather(
+    mut commands: Commands,
+    mut state: ResMut<WeatherState>,
+    mut forces: ResMut<WeatherForces>,
+    weather_q: WeatherEntityQuery,
+) {
+    state.weather = Weather::Clear;
+    state.elapsed = 0.0;
+    *forces = default();
+
+    for entity in weather_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn seed_weather(
+    game_data: Res<GameData>,
+    mut state: ResMut<WeatherState>,
+) {
+    state.rng = fastrand::Rng::with_seed(game_data.seed() ^ SEED_SALT);
+}
+
+/// Emitters are cleaned up with the rest of the particles.
+fn teardown_weather(
+    mut commands: Commands,
+    mut state: ResMut<WeatherState>,
+    mut forces: ResMut<WeatherForces>,
+    announcement_q: Query<Entity, With<WeatherAnnouncement>>,
+) {
+    state.weather = Weather::Clear;
+    *forces = default();
+
+    for entity in announcement_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn change_weather(
+    mut commands: Commands,
+    mut scored: EventReader<BirdScored>,
+    assets: Res<GameAssets>,
+    game_data: Res<GameData>,
+    play_field: Res<PlayField>,
+    mut state: ResMut<WeatherState>,
+    weather_q: WeatherEntityQuery,
+) {
+    if scored.read().count() == 0 || game_data.score % CHANGE_EVERY != 0 {
+        return;
+    }
+
+    let weather = Weather::roll(&mut state.rng);
+    if weather == state.weather {
+        return;
+    }
+
+    debug!("Weather changed to {:?}", weather);
+    state.weather = weather;
+    state.elapsed = 0.0;
+
+    for entity in weather_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    spawn_emitters(&mut commands, weather, &play_field);
+
+    let style = TextStyle {
+        font: assets.font.clone(),
+        font_size: ANNOUNCEMENT_FONT_SIZE,
+        color: ANNOUNCEMENT_HIDDEN,
+    };
+    let show = Sequence::new(fade_text(ANNOUNCEMENT_HIDDEN, Color::WHITE, Duration::from_millis(200)))
+        .then(Delay::new(Duration::from_millis(1200)))
+        .then(fade_text(Color::WHITE, ANNOUNCEMENT_HIDDEN, Duration::from_millis(500)));
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(weather.name(), style)
+                .with_alignment(TextAlignment::Center),
+            transform: Transform::from_translation(Vec3::new(play_field.width / 2.0, 270.0, 50.0)),
+            ..default()
+        })
+        .insert(Name::new("Weather Announcement"))
+        .insert(WeatherAnnouncement)
+        .insert(Animator::new(show).with_completed_id(ANNOUNCEMENT_DONE));
+}
+
+/// Respreads the weather's emitters when the play field changes width.
+fn fit_to_play_field(
+    mut commands: Commands,
+    play_field: Res<PlayField>,
+    state: Res<WeatherState>,
+    emitter_q: Query<Entity, With<WeatherEmitter>>,
+) {
+    for entity in emitter_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_emitters(&mut commands, state.weather, &play_field);
+}
+
+fn fade_text(start: Color, end: Color, duration: Duration) -> Tween<Text> {
+    Tween::new(Ease::QuadOut, duration, TextColorLens { section: 0, start, end })
+}
+
+fn update_weather_forces(
+    time: Res<Time>,
+    game_state: Res<State<GameState>>,
+    settings: Res<WeatherSettings>,
+    mut state: ResMut<WeatherState>,
+    mut forces: ResMut<WeatherForces>,
+) {
+    if *game_state.get() == GameState::Playing {
+        state.elapsed += time.delta_seconds();
+    }
+
+    let new_forces = if settings.affects_flight {
+        match state.weather {
+            Weather::Rain { heavy: true } => WeatherForces {
+                gravity_scale: HEAVY_RAIN_GRAVITY_SCALE,
+                ..default()
+            },
+            Weather::Wind { force } => {
+                // Gusts build from calm to full strength and die back down again.
+                let gust = 0.5 - 0.5 * (state.elapsed * TAU / GUST_PERIOD).cos();
+                WeatherForces {
+                    wind: force * gust,
+                    ..default()
+                }
+            }
+            _ => default(),
+        }
+    } else {
+        default()
+    };
+    *forces = new_forces;
+}
+
+fn despawn_announcements(
+    mut commands: Commands,
+    mut completed: EventReader<TweenCompleted>,
+    announcement_q: Query<(), With<WeatherAnnouncement>>,
+) {
+    for event in completed.read().filter(|event| event.id == ANNOUNCEMENT_DONE) {
+        if announcement_q.contains(event.entity) {
+            commands.entity(event.entity).despawn_recursive();
+        }
+    }
+}
@@ -1,83 +1,326 @@
-use std::fs;
-use std::path::Path;
-
 use bevy::prelude::*;
 use bevy::app::AppExit;
-use bevy::window::PrimaryWindow;
+use bevy::window::{PrimaryWindow, WindowMode};
+use bevy_egui::{egui, EguiContexts, EguiSettings};
 use serde::{Deserialize, Serialize};
 
-use crate::DEFAULT_SCALE;
+use crate::{
+    GAME_SIZE, DEFAULT_SCALE, AppState,
+    menu::MenuScreen,
+    storage,
+    touch,
+    weather::WeatherSettings,
+};
 
 const WINDOW_STATE_FILENAME: &str = "window_state.ron";
+const MIN_SCALE: u8 = 1;
+const MAX_SCALE: u8 = 6;
+/// Bump this and add a step to `WindowState::migrate` when saved fields change meaning.
+const WINDOW_STATE_VERSION: u32 = 1;
 
 #[derive(Clone, Debug, Deserialize, Serialize, Resource)]
 pub struct WindowState {
+    /// Format the state was saved in. Files from before versioning have none, so they count as 0.
+    #[serde(default)]
+    version: u32,
     #[serde(default)]
     pub position: WindowPosition,
+    /// Size of the window when it isn't fullscreen, as a multiple of the game's size.
     #[serde(default)]
     pub scale: u8,
+    #[serde(default)]
+    pub fullscreen: bool,
 }
 
 impl Default for WindowState {
     fn default() -> Self {
         Self {
+            version: WINDOW_STATE_VERSION,
             position: WindowPosition::Automatic,
             scale: DEFAULT_SCALE,
+            fullscreen: false,
         }
     }
 }
 
-pub fn load_window_state() -> WindowState {
-    if Path::new(WINDOW_STATE_FILENAME).is_file() {
-        // TODO: Log errors if these fail and return default.
-        let window_state_str = fs::read_to_string(WINDOW_STATE_FILENAME)
-            .expect("Could not read window state file");
-        ron::from_str(&window_state_str)
-            .expect("Could not deserialize window state")
-    } else {
-        default()
+impl WindowState {
+    /// Brings state saved by older versions up to date.
+    fn migrate(mut self) -> Result<Self, String> {
+        if self.version > WINDOW_STATE_VERSION {
+            return Err(format!("version {} is newer than supported version {}", self.version, WINDOW_STATE_VERSION));
+        }
+
+        if self.version < 1 {
+            // A missing scale used to default to 0.
+            if self.scale == 0 {
+                self.scale = DEFAULT_SCALE;
+            }
+        }
+
+        self.scale = self.scale.clamp(MIN_SCALE, MAX_SCALE);
+        self.version = WINDOW_STATE_VERSION;
+        Ok(self)
+    }
+
+    pub fn mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    fn set_scale(&mut self, window: &mut Window, scale: u8) {
+        self.scale = scale.clamp(MIN_SCALE, MAX_SCALE);
+        if !self.fullscreen {
+            window.resolution.set(GAME_SIZE.0 * self.scale as f32, GAME_SIZE.1 * self.scale as f32);
+        }
+    }
+
+    fn set_fullscreen(&mut self, window: &mut Window, fullscreen: bool) {
+        self.fullscreen = fullscreen;
+        window.mode = self.mode();
+        if !fullscreen {
+            // Go back to the size the window had before.
+            window.resolution.set(GAME_SIZE.0 * self.scale as f32, GAME_SIZE.1 * self.scale as f32);
+        }
+    }
+}
+
+/// Loads the saved window state, falling back to the default if it can't be used. This runs before
+/// logging is set up, so any problem is returned to be logged later by `WindowPlugin`.
+pub fn load_window_state() -> (WindowState, Option<String>) {
+    migrate_or_default(storage::read::<WindowState>(WINDOW_STATE_FILENAME))
+}
+
+fn migrate_or_default(loaded: Result<Option<WindowState>, String>) -> (WindowState, Option<String>) {
+    match loaded.and_then(|state| state.map(WindowState::migrate).transpose()) {
+        Ok(state) => (state.unwrap_or_default(), None),
+        Err(e) => (default(), Some(e)),
     }
 }
 
 pub struct WindowPlugin {
     saved_window_state: WindowState,
+    load_error: Option<String>,
 }
 
 impl WindowPlugin {
-    pub fn new(saved_window_state: WindowState) -> Self {
+    pub fn new(saved_window_state: WindowState, load_error: Option<String>) -> Self {
         Self {
             saved_window_state,
+            load_error,
         }
     }
 }
 
 impl Plugin for WindowPlugin {
     fn build(&self, app: &mut App) {
+        if let Some(e) = &self.load_error {
+            error!("Could not load window state, using defaults: {}", e);
+        }
+
         app
             .insert_resource(self.saved_window_state.clone())
+            .add_systems(Update, check_window_hotkeys)
+            .add_systems(Update, settings_screen_ui
+                .run_if(in_state(AppState::MainMenu))
+                .run_if(resource_equals(MenuScreen::Settings)))
             .add_systems(PostUpdate, update_window_state);
         #[cfg(not(target_arch = "wasm32"))]
         app.add_systems(Last, save_window_state_on_exit.run_if(on_event::<AppExit>()));
     }
 }
 
+/// Largest whole number scale the game fits in the window at, in physical pixels.
+pub fn fit_scale(window: &Window) -> u8 {
+    let fit_x = window.physical_width() as f32 / GAME_SIZE.0;
+    let fit_y = window.physical_height() as f32 / GAME_SIZE.1;
+    fit_x.min(fit_y).floor().clamp(1.0, u8::MAX as f32) as u8
+}
+
 fn update_window_state(
     mut window_state: ResMut<WindowState>,
+    mut egui_settings: ResMut<EguiSettings>,
     window_q: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
 ) {
-    if let Ok(window) = window_q.get_single() {
-        window_state.position = window.position;
+    let Ok(window) = window_q.get_single() else {
+        return;
+    };
+
+    window_state.position = window.position;
+    // Remember the size of a resized window, but not of a fullscreen one.
+    if window.mode == WindowMode::Windowed {
+        let fit_x = window.width() / GAME_SIZE.0;
+        let fit_y = window.height() / GAME_SIZE.1;
+        window_state.scale = (fit_x.min(fit_y).floor() as u8).clamp(MIN_SCALE, MAX_SCALE);
+    }
+
+    // Keep the UI the same size relative to the game, whatever the window's DPI.
+    let scale_factor = fit_scale(window) as f64 / (DEFAULT_SCALE as f64 * window.scale_factor());
+    if egui_settings.scale_factor != scale_factor {
+        egui_settings.scale_factor = scale_factor;
     }
 }
 
+/// F11 toggles fullscreen, and minus and equals shrink and grow the window.
+fn check_window_hotkeys(
+    keys: Res<Input<KeyCode>>,
+    mut window_state: ResMut<WindowState>,
+    mut egui_ctx: EguiContexts,
+    mut window_q: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if egui_ctx.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    let Ok(mut window) = window_q.get_single_mut() else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::F11) {
+        let fullscreen = !window_state.fullscreen;
+        window_state.set_fullscreen(&mut window, fullscreen);
+    }
+    if keys.just_pressed(KeyCode::Minus) {
+        let scale = window_state.scale.saturating_sub(1);
+        window_state.set_scale(&mut window, scale);
+    }
+    if keys.just_pressed(KeyCode::Equals) {
+        let scale = window_state.scale.saturating_add(1);
+        window_state.set_scale(&mut window, scale);
+    }
+}
+
+fn settings_screen_ui(
+    touches: Res<Touches>,
+    egui_settings: Res<EguiSettings>,
+    mut window_state: ResMut<WindowState>,
+    mut weather_settings: ResMut<WeatherSettings>,
+    mut menu_screen: ResMut<MenuScreen>,
+    mut egui_ctx: EguiContexts,
+    mut window_q: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = window_q.get_single_mut() else {
+        return;
+    };
+
+    let egui_window = egui::Window::new("Settings")
+        .title_bar(false)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -50.0]);
+    egui_window.show(egui_ctx.ctx_mut(), |ui| {
+        ui.set_width(250.0);
+        ui.vertical_centered_justified(|ui| {
+            ui.label(egui::RichText::new("Settings").size(30.0));
+
+            ui.label(egui::RichText::new("Window scale").size(20.0));
+            ui.horizontal(|ui| {
+                for choice in MIN_SCALE..=MAX_SCALE {
+                    let is_selected = window_state.scale == choice;
+                    let text = egui::RichText::new(format!("{}x", choice)).size(20.0);
+                    let response = ui.selectable_label(is_selected, text);
+                    let picked = response.clicked() || touch::just_touched(&touches, &egui_settings, response.rect);
+                    if picked && !is_selected {
+                        window_state.set_scale(&mut window, choice);
+                    }
+                }
+            });
+
+            ui.add_space(10.0);
+            ui.label(egui::RichText::new("Fullscreen").size(20.0));
+            ui.horizontal(|ui| {
+                for (choice, text) in [(true, "On"), (false, "Off")] {
+                    let is_selected = window_state.fullscreen == choice;
+                    let response = ui.selectable_label(is_selected, egui::RichText::new(text).size(20.0));
+                    let picked = response.clicked() || touch::just_touched(&touches, &egui_settings, response.rect);
+                    if picked && !is_selected {
+                        window_state.set_fullscreen(&mut window, choice);
+                    }
+                }
+            });
+
+            ui.add_space(10.0);
+            ui.label(egui::RichText::new("Weather affects flight").size(20.0));
+            ui.horizontal(|ui| {
+                for (choice, text) in [(true, "On"), (false, "Off")] {
+                    let is_selected = weather_settings.affects_flight == choice;
+                    let response = ui.selectable_label(is_selected, egui::RichText::new(text).size(20.0));
+                    let picked = response.clicked() || touch::just_touched(&touches, &egui_settings, response.rect);
+                    if picked && !is_selected {
+                        weather_settings.affects_flight = choice;
+                        weather_settings.save();
+                    }
+                }
+            });
+
+            ui.add_space(10.0);
+            let back = ui.button(egui::RichText::new("Back").size(30.0));
+            if back.clicked() || touch::just_touched(&touches, &egui_settings, back.rect) {
+                *menu_screen = MenuScreen::Main;
+            }
+        });
+    });
+}
+
 fn save_window_state_on_exit(
     window_state: Res<WindowState>,
 ) {
     info!("Saving window state");
+    storage::save(WINDOW_STATE_FILENAME, &*window_state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Result<Option<WindowState>, String> {
+        ron::from_str(contents).map(Some).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn pre_version_files_get_the_default_scale() {
+        let (state, error) = migrate_or_default(parse("(position: Automatic, scale: 0)"));
+        assert!(error.is_none());
+        assert_eq!(state.version, WINDOW_STATE_VERSION);
+        assert_eq!(state.scale, DEFAULT_SCALE);
+    }
+
+    #[test]
+    fn pre_version_files_keep_their_scale() {
+        let (state, error) = migrate_or_default(parse("(position: At((10, 20)), scale: 3)"));
+        assert!(error.is_none());
+        assert_eq!(state.scale, 3);
+        assert_eq!(state.position, WindowPosition::At(IVec2::new(10, 20)));
+    }
 
-    let pretty_config = ron::ser::PrettyConfig::default();
-    let state_str = ron::ser::to_string_pretty(&*window_state, pretty_config)
-        .expect("Could not serialize window state");
-    fs::write(WINDOW_STATE_FILENAME, state_str)
-        .expect("Could not write window state to file");
+    #[test]
+    fn out_of_range_scales_are_clamped() {
+        let (state, error) = migrate_or_default(parse("(version: 1, scale: 40)"));
+        assert!(error.is_none());
+        assert_eq!(state.scale, MAX_SCALE);
+    }
+
+    #[test]
+    fn newer_versions_fall_back_to_defaults() {
+        let (state, error) = migrate_or_default(parse("(version: 99, scale: 3, fullscreen: true)"));
+        assert!(error.is_some());
+        assert_eq!(state.scale, DEFAULT_SCALE);
+        assert!(!state.fullscreen);
+    }
+
+    #[test]
+    fn unreadable_files_fall_back_to_defaults() {
+        let (state, error) = migrate_or_default(parse("not ron"));
+        assert!(error.is_some());
+        assert_eq!(state.scale, DEFAULT_SCALE);
+    }
+
+    #[test]
+    fn missing_files_use_defaults_quietly() {
+        let (state, error) = migrate_or_default(Ok(None));
+        assert!(error.is_none());
+        assert_eq!(state.version, WINDOW_STATE_VERSION);
+        assert_eq!(state.scale, DEFAULT_SCALE);
+    }
 }